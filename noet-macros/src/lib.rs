@@ -0,0 +1,379 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, meta::ParseNestedMeta, parenthesized, parse_macro_input, punctuated::Punctuated,
+    DeriveInput, Expr, ExprLit, ItemFn, Lit, LitStr, Meta, Path, Result, Token, Type,
+};
+
+#[derive(Default)]
+struct FunctionOptions {
+    name: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    attributes: Vec<(LitStr, Option<LitStr>)>,
+    ignore_surplus_arguments: bool,
+}
+
+impl FunctionOptions {
+    fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(identifier(meta.value()?.parse()?, true)?);
+        } else if meta.path.is_ident("alias") {
+            self.aliases.push(identifier(meta.value()?.parse()?, true)?);
+        } else if meta.path.is_ident("attribute") {
+            let content;
+            parenthesized!(content in meta.input);
+            let mut values =
+                Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?.into_iter();
+
+            let key = match values.next() {
+                Some(key) => identifier(key, false)?,
+                None => return Err(meta.error("expected an attribute key")),
+            };
+            let docs = values.next();
+            if let Some(extra) = values.next() {
+                return Err(syn::Error::new(extra.span(), "unexpected value"));
+            }
+
+            self.attributes.push((key, docs));
+        } else if meta.path.is_ident("ignore_surplus_arguments") {
+            self.ignore_surplus_arguments = true;
+        } else {
+            return Err(
+                meta.error("expected `name`, `alias`, `attribute` or `ignore_surplus_arguments`")
+            );
+        }
+
+        Ok(())
+    }
+}
+
+// NOTE: these are the characters accepted by `Lexer::identifier`
+fn identifier(name: LitStr, function: bool) -> Result<LitStr> {
+    let value = name.value();
+    let is_valid_segment = |segment: &str| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    let valid = match function {
+        true => value.split('.').all(is_valid_segment),
+        false => is_valid_segment(&value),
+    };
+
+    if !valid {
+        return Err(syn::Error::new(
+            name.span(),
+            format!("'{value}' is not a valid noet identifier"),
+        ));
+    }
+
+    Ok(name)
+}
+
+fn doc_string(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+
+    let docs = lines.join("\n").trim().to_string();
+    (!docs.is_empty()).then_some(docs)
+}
+
+fn quote_option(value: Option<impl quote::ToTokens>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    }
+}
+
+fn metadata_ident(function: &syn::Ident) -> syn::Ident {
+    format_ident!("__noet_metadata_{}", function)
+}
+
+#[proc_macro_attribute]
+pub fn function(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = FunctionOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(args with parser);
+
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand_function(options, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_function(options: FunctionOptions, item: ItemFn) -> Result<TokenStream2> {
+    let Some(name) = options.name else {
+        return Err(syn::Error::new(
+            item.sig.ident.span(),
+            "missing the noet name of the function, add `name = \"...\"`",
+        ));
+    };
+
+    let visibility = &item.vis;
+    let metadata = metadata_ident(&item.sig.ident);
+    let aliases = &options.aliases;
+    let ignore_surplus_arguments = options.ignore_surplus_arguments;
+    let docs = quote_option(
+        doc_string(&item.attrs).map(|docs| quote!(::std::string::String::from(#docs))),
+    );
+    let attributes = options.attributes.iter().map(|(key, docs)| {
+        let docs = quote_option(docs.as_ref());
+        quote!(::noet::metadata::AttributeMetadata::new(#key, #docs))
+    });
+
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #visibility fn #metadata() -> ::noet::metadata::FunctionMetadata {
+            ::noet::metadata::FunctionMetadata {
+                aliases: ::std::vec![#(::std::string::String::from(#aliases)),*],
+                docs: #docs,
+                attributes: ::std::vec![#(#attributes),*],
+                ignore_surplus_arguments: #ignore_surplus_arguments,
+                ..::noet::metadata::FunctionMetadata::new(#name)
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Context, attributes(noet))]
+pub fn derive_context(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_context(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_context(input: DeriveInput) -> Result<TokenStream2> {
+    let mut value: Option<Type> = None;
+    let mut functions: Vec<Path> = vec![];
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("noet"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("value") {
+                value = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("functions") {
+                meta.parse_nested_meta(|function| {
+                    functions.push(function.path);
+                    Ok(())
+                })?;
+            } else {
+                return Err(meta.error("expected `value` or `functions`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    let Some(value) = value else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "missing the value type of the context, add `#[noet(value = ...)]`",
+        ));
+    };
+
+    let registrations = functions.iter().map(|function| {
+        let mut metadata = function.clone();
+        let last = metadata.segments.last_mut().unwrap();
+        last.ident = metadata_ident(&last.ident);
+
+        quote! {
+            registry.register_function_with_metadata(#function, #metadata());
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::noet::context::Context<#value> for #ident #type_generics #where_clause {
+            fn register_functions(registry: &mut ::noet::registry::FunctionRegistry<Self, #value>) {
+                #(#registrations)*
+            }
+        }
+    })
+}
+
+enum FieldKind<'a> {
+    Flag,
+    Optional(&'a Type),
+    Default(Option<Expr>),
+    Required,
+}
+
+fn option_inner(segment: &syn::PathSegment) -> Option<&Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(syn::GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn field_kind(field: &syn::Field, default: Option<Option<Expr>>) -> FieldKind<'_> {
+    let last_segment = match &field.ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last(),
+        _ => None,
+    };
+
+    match (default, last_segment) {
+        (Some(default), _) => FieldKind::Default(default),
+        (None, Some(segment)) if segment.ident == "bool" => FieldKind::Flag,
+        (None, Some(segment)) if segment.ident == "Option" => match option_inner(segment) {
+            Some(inner) => FieldKind::Optional(inner),
+            None => FieldKind::Required,
+        },
+        _ => FieldKind::Required,
+    }
+}
+
+#[proc_macro_derive(FromAttrs, attributes(noet))]
+pub fn derive_from_attrs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_from_attrs(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_from_attrs(input: DeriveInput) -> Result<TokenStream2> {
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "FromAttrs can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut allow_unknown = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("noet"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("allow_unknown") {
+                allow_unknown = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `allow_unknown`"))
+            }
+        })?;
+    }
+
+    let mut keys = vec![];
+    let mut initializers = vec![];
+    let mut attributes = vec![];
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let mut key = LitStr::new(&ident.unraw().to_string().replace('_', "-"), ident.span());
+        let mut default = None;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("noet"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    key = meta.value()?.parse()?;
+                } else if meta.path.is_ident("default") {
+                    default = Some(match meta.input.peek(Token![=]) {
+                        true => Some(meta.value()?.parse()?),
+                        false => None,
+                    });
+                } else {
+                    return Err(meta.error("expected `rename` or `default`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let key = identifier(key, false)?;
+        let field_type = &field.ty;
+        let (value, value_type, required) = match field_kind(field, default) {
+            FieldKind::Flag => (quote!(attrs.get_flag(#key)?), None, false),
+            FieldKind::Optional(inner) => (quote!(attrs.get_value(#key)?), Some(inner), false),
+            FieldKind::Default(None) => (
+                quote!(attrs.get_value(#key)?.unwrap_or_default()),
+                Some(field_type),
+                false,
+            ),
+            FieldKind::Default(Some(default)) => (
+                quote!(attrs.get_value(#key)?.unwrap_or_else(|| #default)),
+                Some(field_type),
+                false,
+            ),
+            FieldKind::Required => (quote!(attrs.require_value(#key)?), Some(field_type), true),
+        };
+
+        let docs = quote_option(doc_string(&field.attrs));
+        let value_type =
+            quote_option(value_type.map(
+                |ty| quote!(::noet::metadata::short_type_name(::core::any::type_name::<#ty>())),
+            ));
+        attributes.push(quote! {
+            ::noet::metadata::AttributeMetadata {
+                value_type: #value_type,
+                required: #required,
+                ..::noet::metadata::AttributeMetadata::new(#key, #docs)
+            }
+        });
+
+        initializers.push(quote!(#ident: #value));
+        keys.push(key);
+    }
+
+    let deny_unknown = (!allow_unknown).then(|| quote!(attrs.deny_unknown(&[#(#keys),*])?;));
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::noet::attribute::FromAttrs for #ident #type_generics #where_clause {
+            fn from_attrs(attrs: ::noet::attribute::Attrs) -> ::noet::error::Result<Self> {
+                #deny_unknown
+
+                ::core::result::Result::Ok(Self {
+                    #(#initializers),*
+                })
+            }
+
+            fn attributes() -> ::std::vec::Vec<::noet::metadata::AttributeMetadata> {
+                ::std::vec![#(#attributes),*]
+            }
+        }
+    })
+}
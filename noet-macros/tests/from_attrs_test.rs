@@ -0,0 +1,135 @@
+use noet::{
+    attribute::{Attrs, FromAttrs},
+    context::Context,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    parser::Parser,
+    registry::FunctionRegistry,
+    value::EmptyValue,
+};
+
+#[derive(Debug, PartialEq, FromAttrs)]
+struct TableAttrs {
+    #[noet(default = 1)]
+    cols: u32,
+    header: bool,
+    caption: Option<String>,
+    #[noet(default)]
+    border_width: u8,
+    #[noet(rename = "id")]
+    identifier: String,
+}
+
+#[derive(Debug, PartialEq, FromAttrs)]
+#[noet(allow_unknown)]
+struct LenientAttrs {
+    lang: Option<String>,
+}
+
+#[derive(Default)]
+struct Tables {
+    tables: Vec<TableAttrs>,
+    langs: Vec<Option<String>>,
+}
+
+impl Context<EmptyValue> for Tables {
+    fn register_functions(registry: &mut FunctionRegistry<Self, EmptyValue>) {
+        registry.register_function(func_table, "table");
+        registry.register_function(func_code, "code");
+        registry.register_function(func_plain, "plain");
+    }
+}
+
+fn func_table(context: &mut Tables, attrs: TableAttrs, _content: String) {
+    context.tables.push(attrs);
+}
+
+fn func_code(context: &mut Tables, attrs: LenientAttrs, _content: String) {
+    context.langs.push(attrs.lang);
+}
+
+fn func_plain(_context: &mut Tables, _attrs: Attrs, _content: String) {}
+
+fn evaluate(input: &str) -> Result<Tables> {
+    let mut context = Tables::default();
+    Evaluator::new().evaluate_document(&mut context, Parser::new(input))?;
+    Ok(context)
+}
+
+#[test]
+fn typed_attributes() -> Result<()> {
+    let context = evaluate(
+        "[#table @id(a) x][#table @header @cols(3) @caption(Scores) @border-width(2) @id(b) y]",
+    )?;
+
+    assert_eq!(
+        context.tables,
+        vec![
+            TableAttrs {
+                cols: 1,
+                header: false,
+                caption: None,
+                border_width: 0,
+                identifier: "a".to_string(),
+            },
+            TableAttrs {
+                cols: 3,
+                header: true,
+                caption: Some("Scores".to_string()),
+                border_width: 2,
+                identifier: "b".to_string(),
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unknown_attributes() -> Result<()> {
+    assert_eq!(
+        evaluate("[#table @id(a) @colls(2) x]").err(),
+        Some(Error::Type(
+            "Unknown attribute 'colls', expected one of: cols, header, caption, border-width, id"
+                .to_string(),
+            Some(16..21)
+        ))
+    );
+
+    let context = evaluate("[#code @lang(rust) @numbered x][#plain @anything x]")?;
+    assert_eq!(context.langs, vec![Some("rust".to_string())]);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_attributes() {
+    assert_eq!(
+        evaluate("[#table x]").err(),
+        Some(Error::Type(
+            "Missing a value for the required attribute 'id'".to_string(),
+            Some(0..10)
+        ))
+    );
+    assert_eq!(
+        evaluate("[#table @id x]").err(),
+        Some(Error::Type(
+            "Missing a value for the required attribute 'id'".to_string(),
+            Some(9..11)
+        ))
+    );
+    assert_eq!(
+        evaluate("[#table @id(a) @header(yes) x]").err(),
+        Some(Error::Type(
+            "Attribute 'header' is a flag and does not take a value".to_string(),
+            Some(23..26)
+        ))
+    );
+    assert_eq!(
+        evaluate("[#table @id(a) @cols(many) x]").err(),
+        Some(Error::Type(
+            "Failed to convert attribute value 'many' to u32".to_string(),
+            Some(21..25)
+        ))
+    );
+}
@@ -0,0 +1,241 @@
+use std::borrow::Cow;
+
+use noet::{
+    attribute::Attrs,
+    context::Context,
+    error::Result,
+    evaluator::Evaluator,
+    metadata::{ArgumentMetadata, AttributeMetadata, ReturnKind, ReturnMetadata},
+    parser::Parser,
+    reference::to_markdown,
+    registry::FunctionRegistry,
+    value::Value,
+    variadic::Variadic,
+};
+
+#[derive(Debug, PartialEq)]
+enum Element {
+    Text(String),
+    Bold(Box<Element>),
+    Table(Vec<Element>, u32),
+    Block(Vec<Element>),
+}
+
+impl<'input> Value<'input> for Element {
+    const LINEBREAK: Option<Self> = None;
+
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self> {
+        Some(Self::Text(text.into_owned()))
+    }
+
+    fn from_block_element(elements: Vec<Self>) -> Option<Self> {
+        Some(Self::Block(elements))
+    }
+}
+
+#[derive(Default, noet::Context)]
+#[noet(
+    value = Element,
+    functions(func_title, format::func_bold, format::func_table, func_list)
+)]
+struct Note {
+    title: Option<String>,
+}
+
+/// Sets the title of the document.
+#[noet::function(name = "title", ignore_surplus_arguments)]
+fn func_title(context: &mut Note, _attrs: Attrs, title: String) {
+    context.title = Some(title);
+}
+
+#[derive(noet::FromAttrs)]
+struct ListAttrs {
+    ordered: bool,
+    /// The number of the first item.
+    start: Option<u32>,
+    marker: String,
+}
+
+#[noet::function(name = "list")]
+fn func_list(_context: &mut Note, attrs: ListAttrs, items: Variadic<Element>) -> Option<Element> {
+    (!attrs.ordered && attrs.start.is_none() && !attrs.marker.is_empty())
+        .then(|| Element::Block(items.into()))
+}
+
+mod format {
+    use super::*;
+
+    /// Makes the text bold.
+    ///
+    /// Works on any element.
+    #[noet::function(name = "b", alias = "bold", alias = "strong")]
+    pub fn func_bold(_context: &mut Note, _attrs: Attrs, elem: Element) -> Element {
+        Element::Bold(Box::new(elem))
+    }
+
+    #[noet::function(
+        name = "table",
+        attribute("cols", "Number of columns"),
+        attribute("header")
+    )]
+    pub fn func_table(
+        _context: &mut Note,
+        attrs: Attrs,
+        items: Variadic<Element>,
+    ) -> Result<Element> {
+        Ok(Element::Table(
+            items.into(),
+            attrs.get_value("cols")?.unwrap_or(1),
+        ))
+    }
+}
+
+fn registry() -> FunctionRegistry<Note, Element> {
+    let mut registry = FunctionRegistry::new();
+    Note::register_functions(&mut registry);
+    registry
+}
+
+#[test]
+fn register_annotated_functions() -> Result<()> {
+    let mut context = Note::default();
+    let evaluator = Evaluator::new();
+    let evaluated = evaluator.evaluate_document(
+        &mut context,
+        Parser::new("[#title Doc | Subtitle][#b a][#bold b][#strong c][#table @cols(2) | x | y]"),
+    )?;
+
+    assert_eq!(context.title, Some("Doc".to_string()));
+    assert_eq!(
+        evaluated,
+        vec![
+            Element::Bold(Box::new(Element::Text("a".to_string()))),
+            Element::Bold(Box::new(Element::Text("b".to_string()))),
+            Element::Bold(Box::new(Element::Text("c".to_string()))),
+            Element::Table(
+                vec![
+                    Element::Text("x".to_string()),
+                    Element::Text("y".to_string())
+                ],
+                2
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn function_metadata() {
+    let registry = registry();
+
+    let title = registry.metadata("title").unwrap();
+    assert_eq!(title.name, "title");
+    assert_eq!(
+        title.docs.as_deref(),
+        Some("Sets the title of the document.")
+    );
+    assert_eq!(title.arguments, vec![ArgumentMetadata::new("String")]);
+    assert_eq!(
+        title.returns,
+        ReturnMetadata::new(ReturnKind::Nothing, false)
+    );
+
+    let bold = registry.metadata("strong").unwrap();
+    assert_eq!(bold.name, "b");
+    assert!(!bold.ignore_surplus_arguments);
+    assert_eq!(bold.aliases, vec!["bold", "strong"]);
+    assert_eq!(
+        bold.docs.as_deref(),
+        Some("Makes the text bold.\n\nWorks on any element.")
+    );
+
+    let table = registry.metadata("table").unwrap();
+    assert_eq!(
+        table.attributes,
+        vec![
+            AttributeMetadata::new("cols", Some("Number of columns")),
+            AttributeMetadata::new("header", None),
+        ]
+    );
+    assert_eq!(table.returns, ReturnMetadata::new(ReturnKind::Value, true));
+
+    let list = registry.metadata("list").unwrap();
+    assert_eq!(
+        list.attributes,
+        vec![
+            AttributeMetadata::new("ordered", None),
+            AttributeMetadata {
+                value_type: Some("u32".to_string()),
+                ..AttributeMetadata::new("start", Some("The number of the first item."))
+            },
+            AttributeMetadata {
+                value_type: Some("String".to_string()),
+                required: true,
+                ..AttributeMetadata::new("marker", None)
+            },
+        ]
+    );
+
+    assert_eq!(registry.metadata("i"), None);
+}
+
+#[test]
+fn markdown_reference() {
+    assert_eq!(
+        to_markdown(&registry()),
+        r#"# Function reference
+
+## `b`
+
+Aliases: `bold`, `strong`
+
+```noet
+[#b Element]
+```
+
+Makes the text bold.
+
+Works on any element.
+
+Returns a value.
+
+## `list`
+
+```noet
+[#list Element...]
+```
+
+Attributes:
+
+- `@ordered`
+- `@start` `u32`: The number of the first item.
+- `@marker` `String` (required)
+
+Returns an optional value.
+
+## `table`
+
+```noet
+[#table Element...]
+```
+
+Attributes:
+
+- `@cols`: Number of columns
+- `@header`
+
+Returns a value and can fail.
+
+## `title`
+
+```noet
+[#title String]
+```
+
+Sets the title of the document.
+
+Returns nothing.
+"#
+    );
+}
@@ -1,9 +1,9 @@
-use std::any::type_name;
+use std::{any::type_name, borrow::Cow};
 
 use crate::{
-    context::Context,
     error::{Error, Result},
     evaluator::Evaluator,
+    metadata::ArgumentMetadata,
     parse_tree::ParsedElement,
     value::Value,
 };
@@ -37,18 +37,23 @@ where
                 None,
             ))?
     }
+
+    fn metadata() -> ArgumentMetadata {
+        ArgumentMetadata::new(type_name::<Self>())
+    }
 }
 
 impl<'input, C, V> Argument<'input, C, V> for V
 where
     V: Value<'input>,
-    C: Context<V>,
 {
     fn from_element(
         evaluator: &Evaluator<C, V>,
         context: &mut C,
         element: ParsedElement<'input>,
     ) -> Result<Self> {
+        let span = element.span().clone();
+
         evaluator
             .evaluate_element(context, element)?
             .ok_or(Error::Eval(
@@ -56,30 +61,86 @@ where
                     "Evaluating argument of type {} did not result in a value",
                     type_name::<V>()
                 ),
-                None,
+                Some(span),
             ))
     }
 }
 
-impl<'input, C, V> Argument<'input, C, V> for ParsedElement<'input> {
+// NOTE: only the owned tree can be an argument, as functions have to accept elements of any
+// input lifetime
+impl<'input, C, V> Argument<'input, C, V> for ParsedElement<'static> {
     fn from_element(
         _evaluator: &Evaluator<C, V>,
         _context: &mut C,
         element: ParsedElement<'input>,
     ) -> Result<Self> {
-        Ok(element)
+        Ok(element.into_owned())
+    }
+}
+
+impl<'input, C, V, T> Argument<'input, C, V> for Option<T>
+where
+    T: Argument<'input, C, V>,
+{
+    fn from_element(
+        evaluator: &Evaluator<C, V>,
+        context: &mut C,
+        element: ParsedElement<'input>,
+    ) -> Result<Self> {
+        T::from_element(evaluator, context, element).map(Some)
+    }
+
+    fn from_elements<I>(
+        evaluator: &Evaluator<C, V>,
+        context: &mut C,
+        elements: &mut I,
+    ) -> Result<Self>
+    where
+        I: Iterator<Item = ParsedElement<'input>>,
+    {
+        elements
+            .next()
+            .map(|v| T::from_element(evaluator, context, v))
+            .transpose()
+    }
+
+    fn metadata() -> ArgumentMetadata {
+        ArgumentMetadata {
+            optional: true,
+            ..T::metadata()
+        }
     }
 }
 
+fn expected_text<T>(element: &ParsedElement) -> Error {
+    let found = match element {
+        ParsedElement::Text(..) => "text",
+        ParsedElement::Function(..) => "a function",
+        ParsedElement::HardLinebreak(..) => "a hard linebreak",
+        ParsedElement::Block(..) => "a block",
+        ParsedElement::Group(..) => "a bracket group",
+        ParsedElement::Comment(..) => "a comment",
+        ParsedElement::Error(..) => "invalid source",
+    };
+
+    Error::Type(
+        format!(
+            "Argument of type {} should be plain text but got {found}",
+            type_name::<T>()
+        ),
+        Some(element.span().clone()),
+    )
+}
+
 impl<'input, C, V> Argument<'input, C, V> for String {
     fn from_element(
-        _evaluator: &Evaluator<C, V>,
+        evaluator: &Evaluator<C, V>,
         _context: &mut C,
         element: ParsedElement<'input>,
     ) -> Result<Self> {
-        match element {
-            ParsedElement::Text(text) => Ok(text.to_string()),
-            _ => panic!(),
+        match evaluator.resolve_text(element)? {
+            ParsedElement::Text(text, _) => Ok(text.into_owned()),
+            element => Err(expected_text::<Self>(&element)),
         }
     }
 }
@@ -91,8 +152,12 @@ impl<'input, C, V> Argument<'input, C, V> for &'input str {
         element: ParsedElement<'input>,
     ) -> Result<Self> {
         match element {
-            ParsedElement::Text(text) => Ok(text),
-            _ => panic!(),
+            ParsedElement::Text(Cow::Borrowed(text), _) => Ok(text),
+            ParsedElement::Text(Cow::Owned(text), span) => Err(Error::Type(
+                format!("Text '{text}' contains escape sequences and cannot be borrowed, use String instead"),
+                Some(span),
+            )),
+            element => Err(expected_text::<Self>(&element)),
         }
     }
 }
@@ -104,21 +169,21 @@ macro_rules! impl_primitive_type {
     ($typ:ty) => {
         impl<'context, 'input, C, V> Argument<'input, C, V> for $typ {
             fn from_element(
-                _evaluator: &Evaluator<C, V>,
+                evaluator: &Evaluator<C, V>,
                 _context: &mut C,
                 element: ParsedElement<'input>,
             ) -> Result<Self> {
-                match element {
-                    ParsedElement::Text(text) => text.parse().map_err(|_| {
+                match evaluator.resolve_text(element)? {
+                    ParsedElement::Text(text, span) => text.parse().map_err(|_| {
                         Error::Type(
                             format!(
                                 "Failed to convert '{text}' to {} in argument",
                                 type_name::<$typ>()
                             ),
-                            None,
+                            Some(span),
                         )
                     }),
-                    _ => panic!(),
+                    element => Err(expected_text::<Self>(&element)),
                 }
             }
         }
@@ -1,24 +1,67 @@
-use std::{any::type_name, fmt::Debug, str::FromStr};
+use std::{
+    any::type_name,
+    borrow::Cow,
+    fmt::{Debug, Display, Formatter},
+    str::FromStr,
+};
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    lexer::Span,
+    metadata::AttributeMetadata,
+    writer::write_attribute,
+};
 
-#[derive(Debug, PartialEq)]
+#[cfg(feature = "macros")]
+pub use noet_macros::FromAttrs;
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute<'input> {
-    pub key: &'input str,
-    pub value: Option<&'input str>,
+    pub key: Cow<'input, str>,
+    pub value: Option<Cow<'input, str>>,
+    pub key_span: Span,
+    pub value_span: Option<Span>,
 }
 
 impl<'input> Attribute<'input> {
-    pub fn new_flag(key: &'input str) -> Self {
-        Self { key, value: None }
+    pub fn new_flag(key: impl Into<Cow<'input, str>>, key_span: Span) -> Self {
+        Self {
+            key: key.into(),
+            value: None,
+            key_span,
+            value_span: None,
+        }
     }
 
-    pub fn new_value(key: &'input str, value: &'input str) -> Self {
+    pub fn new_value(
+        key: impl Into<Cow<'input, str>>,
+        key_span: Span,
+        value: impl Into<Cow<'input, str>>,
+        value_span: Span,
+    ) -> Self {
         Self {
-            key,
-            value: Some(value),
+            key: key.into(),
+            value: Some(value.into()),
+            key_span,
+            value_span: Some(value_span),
         }
     }
+
+    pub fn into_owned(self) -> Attribute<'static> {
+        Attribute {
+            key: self.key.into_owned().into(),
+            value: self.value.map(|value| value.into_owned().into()),
+            key_span: self.key_span,
+            value_span: self.value_span,
+        }
+    }
+}
+
+impl<'input> Display for Attribute<'input> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_attribute(f, self)
+    }
 }
 
 pub struct Attrs<'input> {
@@ -44,8 +87,8 @@ impl<'input> Attrs<'input> {
         self.values
             .iter()
             .find(|x| x.key == key)
-            .and_then(|x| x.value)
-            .map(|x| {
+            .and_then(|x| Some((x.value.as_deref()?, x.value_span.clone())))
+            .map(|(x, span)| {
                 x.parse().map_err(|_| {
                     Error::Type(
                         format!(
@@ -53,10 +96,69 @@ impl<'input> Attrs<'input> {
                             x,
                             type_name::<T>()
                         ),
-                        None,
+                        span,
                     )
                 })
             })
             .transpose()
     }
+
+    pub fn get_flag(&self, key: &str) -> Result<bool> {
+        match self.values.iter().find(|x| x.key == key) {
+            Some(Attribute {
+                value: Some(_),
+                value_span,
+                ..
+            }) => Err(Error::Type(
+                format!("Attribute '{key}' is a flag and does not take a value"),
+                value_span.clone(),
+            )),
+            Some(_) => Ok(true),
+            None => Ok(false),
+        }
+    }
+
+    pub fn require_value<T>(&self, key: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Debug,
+    {
+        match self.get_value(key)? {
+            Some(value) => Ok(value),
+            None => Err(Error::Type(
+                format!("Missing a value for the required attribute '{key}'"),
+                self.values
+                    .iter()
+                    .find(|x| x.key == key)
+                    .map(|x| x.key_span.clone()),
+            )),
+        }
+    }
+
+    pub fn deny_unknown(&self, keys: &[&str]) -> Result<()> {
+        let Some(unknown) = self.values.iter().find(|x| !keys.contains(&x.key.as_ref())) else {
+            return Ok(());
+        };
+
+        let expected = match keys {
+            [] => "no attributes are accepted".to_string(),
+            keys => format!("expected one of: {}", keys.join(", ")),
+        };
+
+        Err(Error::Type(
+            format!("Unknown attribute '{}', {expected}", unknown.key),
+            Some(unknown.key_span.clone()),
+        ))
+    }
+}
+
+pub trait FromAttrs
+where
+    Self: Sized,
+{
+    fn from_attrs(attrs: Attrs) -> Result<Self>;
+
+    fn attributes() -> Vec<AttributeMetadata> {
+        vec![]
+    }
 }
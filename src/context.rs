@@ -1,5 +1,8 @@
 use crate::registry::FunctionRegistry;
 
+#[cfg(feature = "macros")]
+pub use noet_macros::Context;
+
 pub trait Context<Value>
 where
     Self: Sized,
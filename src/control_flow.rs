@@ -0,0 +1,150 @@
+use crate::{
+    argument::Argument,
+    attribute::Attrs,
+    definition::is_valid_name,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    metadata::{ArgumentMetadata, FunctionMetadata, ReturnKind, ReturnMetadata},
+    parse_tree::ParsedElement,
+    registry::FunctionRegistry,
+    value::Value,
+};
+
+pub fn control_flow<C, V>(registry: &mut FunctionRegistry<C, V>)
+where
+    C: 'static,
+    V: Value<'static> + 'static,
+{
+    let returns = ReturnMetadata::new(ReturnKind::OptionalValue, true);
+
+    registry.register_raw_function(
+        func_if,
+        FunctionMetadata {
+            docs: Some(
+                "Evaluates the first branch if the condition holds and the second one otherwise.\n\
+                 A condition is a variable name, optionally negated with `!` or compared to a \
+                 value with `==` or `!=`."
+                    .to_string(),
+            ),
+            arguments: vec![
+                ArgumentMetadata::new("String"),
+                ArgumentMetadata::new("ParsedElement"),
+                ArgumentMetadata {
+                    optional: true,
+                    ..ArgumentMetadata::new("ParsedElement")
+                },
+            ],
+            returns: returns.clone(),
+            ..FunctionMetadata::new("if")
+        },
+    );
+    registry.register_raw_function(
+        func_for,
+        FunctionMetadata {
+            docs: Some(
+                "Evaluates the body for every item of a list, e.g. `[#for tag in tags | ...]`."
+                    .to_string(),
+            ),
+            arguments: vec![
+                ArgumentMetadata::new("String"),
+                ArgumentMetadata::new("ParsedElement"),
+            ],
+            returns,
+            ..FunctionMetadata::new("for")
+        },
+    );
+}
+
+fn is_true<C, V>(evaluator: &Evaluator<C, V>, condition: &str) -> bool {
+    let text = |name: &str| {
+        evaluator
+            .variable(name.trim())
+            .and_then(|variable| variable.as_text().map(|text| text.trim().to_string()))
+    };
+
+    if let Some((name, value)) = condition.split_once("!=") {
+        return text(name).as_deref() != Some(value.trim());
+    }
+    if let Some((name, value)) = condition.split_once("==") {
+        return text(name).as_deref() == Some(value.trim());
+    }
+
+    // NOTE: undefined variables are false, so documents can test for optional front matter
+    let condition = condition.trim();
+    match condition.strip_prefix('!') {
+        Some(name) => !evaluator
+            .variable(name.trim())
+            .is_some_and(|variable| variable.is_truthy()),
+        None => evaluator
+            .variable(condition)
+            .is_some_and(|variable| variable.is_truthy()),
+    }
+}
+
+fn func_if<C, V>(
+    evaluator: &Evaluator<C, V>,
+    context: &mut C,
+    _attrs: Attrs,
+    arguments: Vec<ParsedElement>,
+) -> Result<Option<V>>
+where
+    V: Value<'static>,
+{
+    let mut arguments = arguments.into_iter();
+    let condition: String = Argument::from_elements(evaluator, context, &mut arguments)?;
+    let then: ParsedElement<'static> = Argument::from_elements(evaluator, context, &mut arguments)?;
+    let otherwise: Option<ParsedElement<'static>> =
+        Argument::from_elements(evaluator, context, &mut arguments)?;
+
+    // NOTE: only the branch that is taken gets evaluated
+    let branch = match is_true(evaluator, &condition) {
+        true => Some(then),
+        false => otherwise,
+    };
+
+    match branch {
+        Some(branch) => evaluator.evaluate_element(context, branch),
+        None => Ok(None),
+    }
+}
+
+fn func_for<C, V>(
+    evaluator: &Evaluator<C, V>,
+    context: &mut C,
+    _attrs: Attrs,
+    arguments: Vec<ParsedElement>,
+) -> Result<Option<V>>
+where
+    V: Value<'static>,
+{
+    let mut arguments = arguments.into_iter();
+    let header: String = Argument::from_elements(evaluator, context, &mut arguments)?;
+    let body: ParsedElement<'static> = Argument::from_elements(evaluator, context, &mut arguments)?;
+
+    let (item, list) = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [item, "in", list] if is_valid_name(item) => (item.to_string(), list.to_string()),
+        _ => {
+            return Err(Error::Eval(
+                format!("Expected a loop of the form 'item in list' but got '{header}'"),
+                None,
+            ))
+        }
+    };
+    let Some(list) = evaluator.variable(&list) else {
+        return Err(Error::Eval(
+            format!("Variable '{list}' is not defined"),
+            None,
+        ));
+    };
+
+    let mut values = vec![];
+    for value in list.items() {
+        values.extend(evaluator.evaluate_scoped(
+            context,
+            body.clone(),
+            [(item.as_str(), value)],
+        )?);
+    }
+
+    Ok(V::from_block_element(values))
+}
@@ -0,0 +1,63 @@
+use std::ops::Deref;
+
+use crate::{
+    argument::Argument, error::Result, evaluator::Evaluator, metadata::ArgumentMetadata,
+    parse_tree::ParsedElement,
+};
+
+pub struct Defaulted<T> {
+    inner: T,
+}
+
+impl<'input, C, V, T> Argument<'input, C, V> for Defaulted<T>
+where
+    T: Argument<'input, C, V> + Default,
+{
+    fn from_element(
+        evaluator: &Evaluator<C, V>,
+        context: &mut C,
+        element: ParsedElement<'input>,
+    ) -> Result<Self> {
+        Ok(Defaulted {
+            inner: T::from_element(evaluator, context, element)?,
+        })
+    }
+
+    fn from_elements<I>(
+        evaluator: &Evaluator<C, V>,
+        context: &mut C,
+        elements: &mut I,
+    ) -> Result<Self>
+    where
+        I: Iterator<Item = ParsedElement<'input>>,
+    {
+        Ok(Defaulted {
+            inner: Option::<T>::from_elements(evaluator, context, elements)?.unwrap_or_default(),
+        })
+    }
+
+    fn metadata() -> ArgumentMetadata {
+        ArgumentMetadata {
+            optional: true,
+            ..T::metadata()
+        }
+    }
+}
+
+impl<T> Deref for Defaulted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> Defaulted<T> {
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+
+use crate::{
+    attribute::Attribute,
+    error::{Error, Result},
+    parse_tree::ParsedElement,
+};
+
+pub const DEFINE: &str = "define";
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Parameter(usize),
+}
+
+// NOTE: `$n` refers to the n-th argument and `$$` is a literal '$'
+fn segments(text: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut current = String::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '$' {
+            current.push(c);
+            continue;
+        }
+
+        let digits = text[i + 1..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len() - i - 1);
+
+        match text[i + 1..i + 1 + digits].parse::<usize>() {
+            Ok(index) if index > 0 => {
+                if !current.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut current)));
+                }
+                segments.push(Segment::Parameter(index));
+                for _ in 0..digits {
+                    chars.next();
+                }
+            }
+            _ => {
+                current.push('$');
+                chars.next_if(|(_, c)| *c == '$');
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        segments.push(Segment::Text(current));
+    }
+
+    segments
+}
+
+fn has_parameters(text: &str) -> bool {
+    text.contains('$')
+}
+
+fn count_parameters(element: &ParsedElement) -> usize {
+    match element {
+        ParsedElement::Text(text, _) if has_parameters(text) => segments(text)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Parameter(index) => index,
+                Segment::Text(_) => 0,
+            })
+            .max()
+            .unwrap_or(0),
+        // NOTE: parameters of nested definitions belong to the nested macro
+        ParsedElement::Function(name, ..) if name == DEFINE => 0,
+        ParsedElement::Function(_, _, _, elements, _)
+        | ParsedElement::Block(elements, _)
+        | ParsedElement::Group(elements, _) => {
+            elements.iter().map(count_parameters).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+fn substitute<'input>(
+    element: ParsedElement<'input>,
+    arguments: &[ParsedElement<'input>],
+) -> ParsedElement<'input> {
+    let substitute_all = |elements: Vec<ParsedElement<'input>>| {
+        elements
+            .into_iter()
+            .map(|element| substitute(element, arguments))
+            .collect()
+    };
+
+    match element {
+        ParsedElement::Text(text, span) if has_parameters(&text) => {
+            let mut elements: Vec<_> = segments(&text)
+                .into_iter()
+                .map(|segment| match segment {
+                    Segment::Text(text) => ParsedElement::Text(Cow::Owned(text), span.clone()),
+                    Segment::Parameter(index) => arguments[index - 1].clone(),
+                })
+                .collect();
+
+            match elements.len() {
+                1 => elements.pop().unwrap(),
+                _ => ParsedElement::Block(elements, span),
+            }
+        }
+        ParsedElement::Function(name, name_span, attributes, arguments, span) if name == DEFINE => {
+            ParsedElement::Function(name, name_span, attributes, arguments, span)
+        }
+        ParsedElement::Function(name, name_span, attributes, elements, span) => {
+            ParsedElement::Function(name, name_span, attributes, substitute_all(elements), span)
+        }
+        ParsedElement::Block(elements, span) => {
+            ParsedElement::Block(substitute_all(elements), span)
+        }
+        ParsedElement::Group(elements, span) => {
+            ParsedElement::Group(substitute_all(elements), span)
+        }
+        element => element,
+    }
+}
+
+// NOTE: attributes of a macro call are passed to the functions at the top level of its body
+fn pass_attributes<'input>(element: &mut ParsedElement<'input>, attributes: &[Attribute<'input>]) {
+    match element {
+        ParsedElement::Function(name, _, _, _, _) if name == DEFINE => {}
+        ParsedElement::Function(_, _, function_attributes, _, _) => {
+            function_attributes.extend_from_slice(attributes)
+        }
+        ParsedElement::Block(elements, _) => {
+            for element in elements {
+                pass_attributes(element, attributes);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MacroDefinition {
+    pub name: String,
+    pub body: ParsedElement<'static>,
+    pub parameters: usize,
+}
+
+impl MacroDefinition {
+    pub fn new(name: impl Into<String>, body: ParsedElement<'static>) -> Self {
+        Self {
+            name: name.into(),
+            parameters: count_parameters(&body),
+            body,
+        }
+    }
+
+    pub fn expand<'input>(
+        &self,
+        attributes: Vec<Attribute<'input>>,
+        arguments: Vec<ParsedElement<'input>>,
+    ) -> Result<ParsedElement<'input>> {
+        if arguments.len() != self.parameters {
+            return Err(Error::Type(
+                format!(
+                    "Macro '{}' expects {} argument{} but got {}",
+                    self.name,
+                    self.parameters,
+                    if self.parameters == 1 { "" } else { "s" },
+                    arguments.len()
+                ),
+                arguments.get(self.parameters).map(|a| a.span().clone()),
+            ));
+        }
+
+        // NOTE: attributes are passed before substituting, so they never end up on arguments
+        let mut body = self.body.clone();
+        pass_attributes(&mut body, &attributes);
+
+        Ok(substitute(body, &arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameter_segments() {
+        assert_eq!(
+            segments("a $1, $12$$3 $ $0$"),
+            vec![
+                Segment::Text("a ".to_string()),
+                Segment::Parameter(1),
+                Segment::Text(", ".to_string()),
+                Segment::Parameter(12),
+                Segment::Text("$3 $ $0$".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_parameters() -> Result<()> {
+        let definition = MacroDefinition::new(
+            "pair",
+            ParsedElement::Block(
+                vec![
+                    ParsedElement::Function(
+                        "b".into(),
+                        1..2,
+                        vec![],
+                        vec![ParsedElement::Text("$2".into(), 3..5)],
+                        0..6,
+                    ),
+                    ParsedElement::Text(" and $1".into(), 6..13),
+                ],
+                0..13,
+            ),
+        );
+        assert_eq!(definition.parameters, 2);
+
+        assert_eq!(
+            definition.expand(
+                vec![Attribute::new_flag("x", 20..21)],
+                vec![
+                    ParsedElement::Text("$2".into(), 22..24),
+                    ParsedElement::Text("y".into(), 25..26),
+                ]
+            )?,
+            ParsedElement::Block(
+                vec![
+                    ParsedElement::Function(
+                        "b".into(),
+                        1..2,
+                        vec![Attribute::new_flag("x", 20..21)],
+                        vec![ParsedElement::Text("y".into(), 25..26)],
+                        0..6,
+                    ),
+                    ParsedElement::Block(
+                        vec![
+                            ParsedElement::Text(" and ".into(), 6..13),
+                            ParsedElement::Text("$2".into(), 22..24),
+                        ],
+                        6..13
+                    ),
+                ],
+                0..13,
+            )
+        );
+
+        Ok(())
+    }
+}
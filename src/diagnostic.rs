@@ -0,0 +1,178 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{error::Error, lexer::Span};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn from_offset(input: &str, offset: usize) -> Self {
+        let offset = Self::clamp_offset(input, offset);
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    fn clamp_offset(input: &str, offset: usize) -> usize {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    input: &'a str,
+    hint: Option<String>,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(error: &'a Error, input: &'a str) -> Self {
+        Self {
+            error,
+            input,
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.error
+            .span()
+            .map(|span| Location::from_offset(self.input, span.start))
+    }
+
+    fn write_snippet(&self, f: &mut Formatter<'_>, span: &Span) -> std::fmt::Result {
+        let start = Location::clamp_offset(self.input, span.start);
+        let end = Location::clamp_offset(self.input, span.end.max(start));
+
+        let line_start = self.input[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.input[start..]
+            .find('\n')
+            .map_or(self.input.len(), |i| start + i);
+        let line = self.input[line_start..line_end].trim_end_matches('\r');
+
+        let location = Location::from_offset(self.input, start);
+        let gutter = " ".repeat(location.line.to_string().len());
+
+        // NOTE: tabs are kept in the padding so the carets line up with the source line
+        let padding: String = self.input[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let underline = self.input[start..end.min(line_end)].chars().count().max(1);
+
+        writeln!(f, "{gutter}--> {location}")?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {line}", location.line)?;
+        write!(f, "{gutter} | {padding}{}", "^".repeat(underline))
+    }
+}
+
+impl<'a> Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: {}", self.error)?;
+
+        if let Some(span) = self.error.span() {
+            writeln!(f)?;
+            self.write_snippet(f, span)?;
+        }
+
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  = hint: {hint}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_from_offset() {
+        let input = "first line\nsecond ëine\nthird";
+
+        assert_eq!(
+            Location::from_offset(input, 0),
+            Location { line: 1, column: 1 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 11),
+            Location { line: 2, column: 1 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 20),
+            Location { line: 2, column: 9 }
+        );
+        assert_eq!(
+            Location::from_offset(input, 100),
+            Location { line: 3, column: 6 }
+        );
+    }
+
+    #[test]
+    fn render_with_snippet() {
+        let input = "[#title Document]\n[#table @cols(two) a | b]";
+        let error = Error::Type(
+            "Failed to convert attribute value 'two' to u32".to_string(),
+            Some(32..35),
+        );
+
+        assert_eq!(
+            Diagnostic::new(&error, input)
+                .with_hint("use a number")
+                .to_string(),
+            "error: Type error: Failed to convert attribute value 'two' to u32
+ --> 2:15
+  |
+2 | [#table @cols(two) a | b]
+  |               ^^^
+  = hint: use a number"
+        );
+    }
+
+    #[test]
+    fn render_without_span() {
+        let error = Error::Eval("Something went wrong".to_string(), None);
+
+        assert_eq!(
+            Diagnostic::new(&error, "").to_string(),
+            "error: Eval error: Something went wrong"
+        );
+    }
+
+    #[test]
+    fn render_span_at_end_of_input() {
+        let input = "[#title Document";
+        let error = Error::Parse("Reached EOF".to_string(), Some(16..16));
+
+        assert_eq!(
+            Diagnostic::new(&error, input).to_string(),
+            "error: Parse error: Reached EOF
+ --> 1:17
+  |
+1 | [#title Document
+  |                 ^"
+        );
+    }
+}
@@ -5,12 +5,36 @@ use crate::lexer::Span;
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     Parse(String, Option<Span>),
     Type(String, Option<Span>),
     Eval(String, Option<Span>),
 }
 
+impl Error {
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Parse(message, _) | Error::Type(message, _) | Error::Eval(message, _) => message,
+        }
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Error::Parse(_, span) | Error::Type(_, span) | Error::Eval(_, span) => span.as_ref(),
+        }
+    }
+
+    pub fn or_span(self, span: Span) -> Self {
+        match self {
+            Error::Parse(message, None) => Error::Parse(message, Some(span)),
+            Error::Type(message, None) => Error::Type(message, Some(span)),
+            Error::Eval(message, None) => Error::Eval(message, Some(span)),
+            error => error,
+        }
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1,14 +1,82 @@
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
 use crate::{
     attribute::{Attribute, Attrs},
     context::Context,
+    definition::{is_valid_name, MacroDefinition, DEFINE},
     error::{Error, Result},
+    lexer::Span,
     parse_tree::ParsedElement,
     registry::FunctionRegistry,
+    scope::{Scope, Variable, GET, SET},
     value::Value,
 };
 
+pub type FallbackHandler<Context, Value> = Box<
+    dyn Fn(
+        &Evaluator<Context, Value>,
+        &mut Context,
+        &str,
+        Attrs,
+        Vec<ParsedElement>,
+    ) -> Result<Option<Value>>,
+>;
+
+#[derive(Default)]
+pub enum UnknownFunctionPolicy<Context, Value> {
+    #[default]
+    Error,
+    // NOTE: the arguments of the unknown function are rendered in its place
+    Warn(Box<dyn Fn(&Error)>),
+    Drop,
+    Handler(FallbackHandler<Context, Value>),
+}
+
+impl<Context, Value> UnknownFunctionPolicy<Context, Value> {
+    pub fn warn<F>(warn: F) -> Self
+    where
+        F: Fn(&Error) + 'static,
+    {
+        Self::Warn(Box::new(warn))
+    }
+
+    pub fn handler<F>(handler: F) -> Self
+    where
+        F: Fn(
+                &Evaluator<Context, Value>,
+                &mut Context,
+                &str,
+                Attrs,
+                Vec<ParsedElement>,
+            ) -> Result<Option<Value>>
+            + 'static,
+    {
+        Self::Handler(Box::new(handler))
+    }
+}
+
+pub const DEFAULT_MACRO_RECURSION_LIMIT: usize = 64;
+pub const DEFAULT_MACRO_EXPANSION_LIMIT: usize = 10_000;
+const MAX_EVALUATION_DEPTH: usize = 256;
+const MAX_VARIABLE_SIZE: usize = 10_000;
+
 pub struct Evaluator<Context, Value> {
     function_registry: FunctionRegistry<Context, Value>,
+    unknown_function_policy: UnknownFunctionPolicy<Context, Value>,
+    // NOTE: macros are defined while evaluating, so they live behind a shared reference
+    macros: RefCell<HashMap<String, Rc<MacroDefinition>>>,
+    macro_depth: Cell<usize>,
+    macro_recursion_limit: usize,
+    macro_expansions: Cell<usize>,
+    macro_expansion_limit: usize,
+    depth: Cell<usize>,
+    variables: HashMap<String, Variable>,
+    scope: RefCell<Scope>,
 }
 
 impl<'input, C, V> Default for Evaluator<C, V>
@@ -30,7 +98,162 @@ where
         let mut function_registry = FunctionRegistry::new();
         C::register_functions(&mut function_registry);
 
-        Self { function_registry }
+        Self::with_registry(function_registry)
+    }
+}
+
+impl<C, V> Evaluator<C, V> {
+    pub fn with_registry(function_registry: FunctionRegistry<C, V>) -> Self {
+        Self {
+            function_registry,
+            unknown_function_policy: UnknownFunctionPolicy::Error,
+            macros: RefCell::new(HashMap::new()),
+            macro_depth: Cell::new(0),
+            macro_recursion_limit: DEFAULT_MACRO_RECURSION_LIMIT,
+            macro_expansions: Cell::new(0),
+            macro_expansion_limit: DEFAULT_MACRO_EXPANSION_LIMIT,
+            depth: Cell::new(0),
+            variables: HashMap::new(),
+            scope: RefCell::new(Scope::new()),
+        }
+    }
+
+    pub fn registry(&self) -> &FunctionRegistry<C, V> {
+        &self.function_registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut FunctionRegistry<C, V> {
+        &mut self.function_registry
+    }
+
+    pub fn unknown_function_policy(&self) -> &UnknownFunctionPolicy<C, V> {
+        &self.unknown_function_policy
+    }
+
+    pub fn set_unknown_function_policy(&mut self, policy: UnknownFunctionPolicy<C, V>) {
+        self.unknown_function_policy = policy;
+    }
+
+    pub fn set_macro_recursion_limit(&mut self, limit: usize) {
+        self.macro_recursion_limit = limit;
+    }
+
+    pub fn set_macro_expansion_limit(&mut self, limit: usize) {
+        self.macro_expansion_limit = limit;
+    }
+
+    pub fn macro_definition(&self, name: &str) -> Option<Rc<MacroDefinition>> {
+        self.macros.borrow().get(name).cloned()
+    }
+
+    // NOTE: seeded variables, e.g. from front matter, are shadowed by the ones set in a document
+    pub fn set_variable(&mut self, name: impl Into<String>, value: impl Into<Variable>) {
+        self.variables.insert(name.into(), value.into());
+    }
+
+    pub fn variable(&self, name: &str) -> Option<Variable> {
+        self.scope
+            .borrow()
+            .get(name)
+            .or_else(|| self.variables.get(name))
+            .cloned()
+    }
+
+    fn lookup_variable<'input>(
+        &self,
+        arguments: &[ParsedElement<'input>],
+        span: Span,
+    ) -> Result<ParsedElement<'input>> {
+        let [ParsedElement::Text(name, name_span)] = arguments else {
+            return Err(Error::Eval(
+                "A variable reference expects a name".to_string(),
+                Some(span),
+            ));
+        };
+
+        let name = name.trim();
+        match self.variable(name) {
+            Some(value) => Ok(value.to_element(span)),
+            None => Err(Error::Eval(
+                format!("Variable '{name}' is not defined"),
+                Some(name_span.clone()),
+            )),
+        }
+    }
+
+    // NOTE: references in a value are resolved when it is set, so values are scoped lexically
+    // and can refer to the previous value of the same variable
+    fn resolve_variables<'input>(
+        &self,
+        element: ParsedElement<'input>,
+    ) -> Result<ParsedElement<'input>> {
+        let resolve_all = |elements: Vec<ParsedElement<'input>>| {
+            elements
+                .into_iter()
+                .map(|element| self.resolve_variables(element))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        match element {
+            ParsedElement::Function(name, _, _, arguments, span)
+                if name == GET && !self.function_registry.contains(GET) =>
+            {
+                self.lookup_variable(&arguments, span)
+            }
+            // NOTE: raw functions decide themselves which of their arguments get evaluated, so
+            // references in them are resolved when they are evaluated
+            ParsedElement::Function(ref name, ..)
+                if name == DEFINE
+                    || self
+                        .function_registry
+                        .metadata(name)
+                        .is_some_and(|metadata| metadata.raw) =>
+            {
+                Ok(element)
+            }
+            ParsedElement::Function(name, name_span, attributes, arguments, span) => Ok(
+                ParsedElement::Function(name, name_span, attributes, resolve_all(arguments)?, span),
+            ),
+            ParsedElement::Block(elements, span) => {
+                Ok(ParsedElement::Block(resolve_all(elements)?, span))
+            }
+            ParsedElement::Group(elements, span) => {
+                Ok(ParsedElement::Group(resolve_all(elements)?, span))
+            }
+            element => Ok(element),
+        }
+    }
+
+    // NOTE: variables can be passed where text is expected, e.g. `[#link [#get url]]`
+    pub fn resolve_text<'input>(
+        &self,
+        element: ParsedElement<'input>,
+    ) -> Result<ParsedElement<'input>> {
+        match self.resolve_variables(element)? {
+            ParsedElement::Block(elements, span)
+                if !elements.is_empty()
+                    && elements
+                        .iter()
+                        .all(|element| matches!(element, ParsedElement::Text(..))) =>
+            {
+                let text = elements
+                    .iter()
+                    .filter_map(|element| match element {
+                        ParsedElement::Text(text, _) => Some(text.as_ref()),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                Ok(ParsedElement::Text(Cow::Owned(text), span))
+            }
+            element => Ok(element),
+        }
+    }
+
+    fn scoped<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.scope.borrow_mut().push();
+        let result = f();
+        self.scope.borrow_mut().pop();
+        result
     }
 }
 
@@ -41,28 +264,304 @@ where
     fn evaluate_function(
         &self,
         context: &mut Context,
-        name: &'input str,
+        name: Cow<'input, str>,
+        name_span: Span,
         attributes: Vec<Attribute<'input>>,
         arguments: Vec<ParsedElement<'input>>,
+        span: Span,
     ) -> Result<Option<V>> {
-        match self.function_registry.get(name) {
-            Some(func) => func(self, context, Attrs::new(attributes), arguments),
-            None => Err(Error::Eval(format!("Function '{name}' not found"), None)),
+        if let Some(func) = self.function_registry.get(&name) {
+            self.check_arity(&name, &arguments)?;
+            // NOTE: variables set in the arguments of a function are local to that function
+            return self
+                .scoped(|| func(self, context, Attrs::new(attributes), arguments))
+                .map_err(|e| e.or_span(span));
+        }
+
+        // NOTE: registered functions take precedence over the built-in functions
+        match name.as_ref() {
+            DEFINE => return self.define_macro(arguments, span),
+            SET => return self.evaluate_set(arguments, span),
+            GET => return self.evaluate_get(context, arguments, span),
+            _ => {}
+        }
+
+        match self.macro_definition(&name) {
+            Some(definition) => {
+                self.evaluate_macro(context, &definition, attributes, arguments, span)
+            }
+            None => self
+                .evaluate_unknown_function(context, &name, name_span, attributes, arguments, span),
+        }
+    }
+
+    fn define_macro(&self, arguments: Vec<ParsedElement<'input>>, span: Span) -> Result<Option<V>> {
+        let mut arguments = arguments.into_iter();
+        let (Some(ParsedElement::Text(name, name_span)), Some(body), None) =
+            (arguments.next(), arguments.next(), arguments.next())
+        else {
+            return Err(Error::Eval(
+                "A macro definition expects a name and a body".to_string(),
+                Some(span),
+            ));
+        };
+
+        let name = name.trim();
+        if !is_valid_name(name) {
+            return Err(Error::Eval(
+                format!("Invalid macro name '{name}'"),
+                Some(name_span),
+            ));
+        }
+        // NOTE: documents cannot replace the functions of the context or the built-in functions
+        if [DEFINE, SET, GET].contains(&name) || self.function_registry.contains(name) {
+            return Err(Error::Eval(
+                format!("Cannot define macro '{name}', a function with that name already exists"),
+                Some(name_span),
+            ));
         }
+
+        self.macros.borrow_mut().insert(
+            name.to_string(),
+            Rc::new(MacroDefinition::new(name, body.into_owned())),
+        );
+
+        Ok(None)
+    }
+
+    fn evaluate_macro(
+        &self,
+        context: &mut Context,
+        definition: &MacroDefinition,
+        attributes: Vec<Attribute<'input>>,
+        arguments: Vec<ParsedElement<'input>>,
+        span: Span,
+    ) -> Result<Option<V>> {
+        let depth = self.macro_depth.get();
+        if depth >= self.macro_recursion_limit {
+            return Err(Error::Eval(
+                format!(
+                    "Macro '{}' exceeded the recursion limit of {}",
+                    definition.name, self.macro_recursion_limit
+                ),
+                Some(span),
+            ));
+        }
+
+        // NOTE: macros that call other macros several times grow exponentially, so the total
+        // number of expansions in a document is limited as well
+        let expansions = self.macro_expansions.get();
+        if expansions >= self.macro_expansion_limit {
+            return Err(Error::Eval(
+                format!(
+                    "Macro '{}' exceeded the expansion limit of {}",
+                    definition.name, self.macro_expansion_limit
+                ),
+                Some(span),
+            ));
+        }
+        self.macro_expansions.set(expansions + 1);
+
+        let expansion = definition
+            .expand(attributes, arguments)
+            .map_err(|e| e.or_span(span))?;
+
+        self.macro_depth.set(depth + 1);
+        let result = self.scoped(|| self.evaluate_element(context, expansion));
+        self.macro_depth.set(depth);
+
+        result
+    }
+
+    fn evaluate_set(&self, arguments: Vec<ParsedElement<'input>>, span: Span) -> Result<Option<V>> {
+        let mut arguments = arguments.into_iter();
+        let (Some(ParsedElement::Text(name, name_span)), Some(value), None) =
+            (arguments.next(), arguments.next(), arguments.next())
+        else {
+            return Err(Error::Eval(
+                "A variable definition expects a name and a value".to_string(),
+                Some(span),
+            ));
+        };
+
+        let name = name.trim();
+        if !is_valid_name(name) {
+            return Err(Error::Eval(
+                format!("Invalid variable name '{name}'"),
+                Some(name_span),
+            ));
+        }
+
+        let value = self.resolve_variables(value)?;
+        // NOTE: values that refer to themselves can grow with every assignment, limiting their
+        // size and depth keeps both copying and evaluating them bounded
+        let (depth, size) = measure(&value);
+        if depth > MAX_EVALUATION_DEPTH || size > MAX_VARIABLE_SIZE {
+            return Err(Error::Eval(
+                format!(
+                    "Variable '{name}' exceeds the limit of {MAX_VARIABLE_SIZE} elements \
+                     or {MAX_EVALUATION_DEPTH} levels"
+                ),
+                Some(span),
+            ));
+        }
+
+        self.scope.borrow_mut().set(name, value);
+
+        Ok(None)
+    }
+
+    fn evaluate_get(
+        &self,
+        context: &mut Context,
+        arguments: Vec<ParsedElement<'input>>,
+        span: Span,
+    ) -> Result<Option<V>> {
+        let value = self.lookup_variable(&arguments, span)?;
+        self.evaluate_element(context, value)
+    }
+
+    fn evaluate_unknown_function(
+        &self,
+        context: &mut Context,
+        name: &str,
+        name_span: Span,
+        attributes: Vec<Attribute<'input>>,
+        arguments: Vec<ParsedElement<'input>>,
+        span: Span,
+    ) -> Result<Option<V>> {
+        let error = Error::Eval(format!("Function '{name}' not found"), Some(name_span));
+
+        match &self.unknown_function_policy {
+            UnknownFunctionPolicy::Error => Err(error),
+            UnknownFunctionPolicy::Warn(warn) => {
+                warn(&error);
+
+                let mut values = vec![];
+                for argument in arguments {
+                    values.extend(self.evaluate_element(context, argument)?);
+                }
+                Ok(V::from_block_element(values))
+            }
+            UnknownFunctionPolicy::Drop => Ok(None),
+            UnknownFunctionPolicy::Handler(handler) => {
+                handler(self, context, name, Attrs::new(attributes), arguments)
+                    .map_err(|e| e.or_span(span))
+            }
+        }
+    }
+
+    fn check_arity(&self, name: &str, arguments: &[ParsedElement]) -> Result<()> {
+        let Some(metadata) = self.function_registry.metadata(name) else {
+            return Ok(());
+        };
+
+        match metadata.max_arguments() {
+            Some(expected) if !metadata.ignore_surplus_arguments && arguments.len() > expected => {
+                Err(Error::Type(
+                    format!(
+                        "Function '{name}' expects {expected} argument{} but got {}",
+                        if expected == 1 { "" } else { "s" },
+                        arguments.len()
+                    ),
+                    Some(arguments[expected].span().clone()),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn evaluate_group(
+        &self,
+        context: &mut Context,
+        elements: Vec<ParsedElement<'input>>,
+        span: Span,
+    ) -> Result<Option<V>> {
+        if let Some(handler) = self.function_registry.group_handler() {
+            // NOTE: trees that were not parsed can have empty spans, so the span of the block is
+            // taken from its elements, like the parser does for arguments
+            let content = match (elements.first(), elements.last()) {
+                (Some(_), _) if elements.len() == 1 => elements.into_iter().next().unwrap(),
+                (Some(first), Some(last)) => {
+                    let block_span = first.span().start..last.span().end;
+                    ParsedElement::Block(elements, block_span)
+                }
+                _ => {
+                    let inner = span.start.saturating_add(1).min(span.end);
+                    ParsedElement::Block(elements, inner..inner)
+                }
+            };
+
+            return self
+                .scoped(|| handler(self, context, Attrs::new(vec![]), vec![content]))
+                .map_err(|e| e.or_span(span));
+        }
+
+        // NOTE: without a group handler, the brackets are turned back into literal text
+        let mut values = vec![];
+        values.extend(V::from_text_element(Cow::Borrowed("[")));
+        for element in elements {
+            values.extend(self.evaluate_element(context, element)?);
+        }
+        values.extend(V::from_text_element(Cow::Borrowed("]")));
+
+        Ok(V::from_block_element(values))
+    }
+
+    pub fn evaluate_scoped<I, K>(
+        &self,
+        context: &mut Context,
+        element: ParsedElement<'input>,
+        variables: I,
+    ) -> Result<Option<V>>
+    where
+        I: IntoIterator<Item = (K, Variable)>,
+        K: Into<String>,
+    {
+        self.scoped(|| {
+            for (name, value) in variables {
+                self.scope.borrow_mut().set(name, value);
+            }
+            self.evaluate_element(context, element)
+        })
     }
 
     pub fn evaluate_element(
         &self,
         context: &mut Context,
         element: ParsedElement<'input>,
+    ) -> Result<Option<V>> {
+        // NOTE: macros and variables can nest deeper than the parser allows, limiting the
+        // evaluation depth prevents them from overflowing the stack
+        let depth = self.depth.get();
+        if depth >= MAX_EVALUATION_DEPTH {
+            return Err(Error::Eval(
+                format!(
+                    "Evaluation can not be nested more than {MAX_EVALUATION_DEPTH} levels deep"
+                ),
+                Some(element.span().clone()),
+            ));
+        }
+
+        self.depth.set(depth + 1);
+        let result = self.evaluate_nested_element(context, element);
+        self.depth.set(depth);
+
+        result
+    }
+
+    fn evaluate_nested_element(
+        &self,
+        context: &mut Context,
+        element: ParsedElement<'input>,
     ) -> Result<Option<V>> {
         match element {
-            ParsedElement::HardLinebreak() => Ok(V::LINEBREAK),
-            ParsedElement::Text(t) => Ok(V::from_text_element(t)),
-            ParsedElement::Function(name, attributes, arguments) => {
-                self.evaluate_function(context, name, attributes, arguments)
+            ParsedElement::HardLinebreak(_) => Ok(V::LINEBREAK),
+            ParsedElement::Text(t, _) => Ok(V::from_text_element(t)),
+            ParsedElement::Function(name, name_span, attributes, arguments, span) => {
+                self.evaluate_function(context, name, name_span, attributes, arguments, span)
             }
-            ParsedElement::Block(elements) => Ok(V::from_block_element(
+            ParsedElement::Block(elements, _) => Ok(V::from_block_element(
                 elements
                     .into_iter()
                     .map(|e| self.evaluate_element(context, e))
@@ -71,10 +570,40 @@ where
                     .flatten()
                     .collect(),
             )),
+            ParsedElement::Group(elements, span) => self.evaluate_group(context, elements, span),
+            ParsedElement::Comment(..) => Ok(None),
+            ParsedElement::Error(span) => Err(Error::Eval(
+                "Cannot evaluate source that failed to parse".to_string(),
+                Some(span),
+            )),
         }
     }
 
     pub fn evaluate_document<I>(&self, context: &mut Context, document: I) -> Result<Vec<V>>
+    where
+        I: Iterator<Item = Result<ParsedElement<'input>>>,
+    {
+        // NOTE: macros and variables are scoped to the document they are defined in, a document
+        // evaluated by a function, e.g. an include, restores the ones of the outer document after
+        // it is done, while its macro expansions count towards the limit of the outer document
+        let nested = self.depth.get() > 0;
+        let macros = self.macros.take();
+        let scope = self.scope.replace(Scope::new());
+        if !nested {
+            self.macro_expansions.set(0);
+        }
+
+        let result = self.evaluate_elements(context, document);
+
+        if nested {
+            *self.macros.borrow_mut() = macros;
+            *self.scope.borrow_mut() = scope;
+        }
+
+        result
+    }
+
+    fn evaluate_elements<I>(&self, context: &mut Context, document: I) -> Result<Vec<V>>
     where
         I: Iterator<Item = Result<ParsedElement<'input>>>,
     {
@@ -88,3 +617,18 @@ where
         Ok(evaluated_elements)
     }
 }
+
+fn measure(element: &ParsedElement) -> (usize, usize) {
+    let children: &[ParsedElement] = match element {
+        ParsedElement::Function(_, _, _, arguments, _) => arguments,
+        ParsedElement::Block(elements, _) | ParsedElement::Group(elements, _) => elements,
+        _ => &[],
+    };
+
+    children
+        .iter()
+        .map(measure)
+        .fold((1, 1), |(depth, size), (child_depth, child_size)| {
+            (depth.max(child_depth + 1), size + child_size)
+        })
+}
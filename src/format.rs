@@ -0,0 +1,411 @@
+use crate::{
+    error::Result,
+    lexer::TokenType,
+    parser::Parser,
+    syntax::{SyntaxElement, SyntaxKind, SyntaxNode},
+};
+
+const DEFAULT_WIDTH: usize = 80;
+const INDENT_WIDTH: usize = 2;
+
+#[derive(Debug, PartialEq)]
+enum Atom {
+    Space,
+    Text(String),
+    HardLinebreak,
+    LineComment(String),
+}
+
+pub struct Formatter {
+    width: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+        }
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn format(&self, input: &str) -> Result<String> {
+        Parser::new(input).collect::<Result<Vec<_>>>()?;
+
+        let document = SyntaxNode::parse(input);
+        let atoms = trim(self.atoms(&document.children, 0));
+
+        let mut output = String::new();
+        layout(&atoms, &mut output, 0, Some(self.width));
+
+        if !output.is_empty() {
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    fn atoms(&self, elements: &[SyntaxElement], indent: usize) -> Vec<Atom> {
+        let mut atoms = vec![];
+
+        for element in elements {
+            let atom = match element {
+                SyntaxElement::Token(token) => match token.token_type {
+                    TokenType::Whitespace => Atom::Space,
+                    TokenType::HardLinebreak => Atom::HardLinebreak,
+                    TokenType::Comment if token.text.starts_with("%%") => {
+                        Atom::LineComment(token.text.to_string())
+                    }
+                    _ => Atom::Text(token.text.to_string()),
+                },
+                SyntaxElement::Node(node) => match node.kind {
+                    SyntaxKind::Function => Atom::Text(self.function(node, indent)),
+                    SyntaxKind::Group => Atom::Text(self.group(node, indent)),
+                    _ => Atom::Text(node.text()),
+                },
+            };
+
+            match (atoms.last_mut(), atom) {
+                (Some(Atom::Text(previous)), Atom::Text(text)) => previous.push_str(&text),
+                (Some(Atom::Space), Atom::Space) => {}
+                (_, atom) => atoms.push(atom),
+            }
+        }
+
+        atoms
+    }
+
+    fn group(&self, node: &SyntaxNode, indent: usize) -> String {
+        let content = match node.children.as_slice() {
+            [_, content @ .., SyntaxElement::Token(close)]
+                if close.token_type == TokenType::RightBracket =>
+            {
+                content
+            }
+            [_, content @ ..] => content,
+            [] => &[],
+        };
+
+        let mut output = String::from("[");
+        if layout(&self.atoms(content, indent), &mut output, indent, None) {
+            output.push(' ');
+        }
+        output.push(']');
+        output
+    }
+
+    fn function(&self, node: &SyntaxNode, indent: usize) -> String {
+        let multiline = self.is_multiline(node);
+        let content_indent = if multiline {
+            indent + INDENT_WIDTH
+        } else {
+            indent
+        };
+
+        let mut output = String::from("[");
+        let mut arguments = vec![];
+        // NOTE: the first argument of a multiline call always starts a new row
+        let mut new_row = true;
+
+        for child in &node.children {
+            match child {
+                SyntaxElement::Token(token) => match token.token_type {
+                    TokenType::FunctionIdentifier => output.push_str(token.text),
+                    TokenType::Comment if arguments.is_empty() => {
+                        separate(&mut output, indent);
+                        output.push_str(token.text);
+                        new_row |= token.text.ends_with('\n');
+                    }
+                    TokenType::Whitespace | TokenType::Comment => {
+                        new_row |= token.text.contains('\n')
+                    }
+                    _ => {}
+                },
+                SyntaxElement::Node(node) if node.kind == SyntaxKind::Attribute => {
+                    separate(&mut output, indent);
+                    output.push_str(&node.text());
+                }
+                SyntaxElement::Node(node) => {
+                    let atoms = trim(self.atoms(&node.children, content_indent));
+                    // NOTE: an empty argument has no content to start a row with
+                    new_row |= !atoms.is_empty() && leading_newline(&node.children);
+                    arguments.push((atoms, new_row));
+                    new_row = trailing_newline(&node.children);
+                }
+            }
+        }
+
+        let width = multiline.then_some(self.width);
+        let mut previous_empty = false;
+
+        for (i, (atoms, new_row)) in arguments.iter().enumerate() {
+            if multiline && *new_row {
+                newline(&mut output, indent);
+                output.push('|');
+            } else if i == 0 && (atoms.is_empty() || starts_with_comment(atoms)) {
+                output.push_str(" |");
+            } else if i > 0 {
+                output.push_str(if previous_empty && !multiline {
+                    "|"
+                } else {
+                    " |"
+                });
+            }
+
+            // NOTE: an empty argument still needs whitespace, otherwise the separators are merged
+            let last = i == arguments.len() - 1;
+            if !atoms.is_empty() || (!multiline && (i == 0 || last)) {
+                output.push(' ');
+            }
+
+            layout(atoms, &mut output, content_indent, width);
+            previous_empty = atoms.is_empty();
+        }
+
+        if multiline {
+            newline(&mut output, indent);
+        }
+        output.push(']');
+        output
+    }
+
+    fn is_multiline(&self, node: &SyntaxNode) -> bool {
+        node.children.iter().any(|child| match child {
+            SyntaxElement::Token(_) => contains_newline(child),
+            SyntaxElement::Node(node) if node.kind == SyntaxKind::Argument => {
+                node.children.iter().any(|child| match child {
+                    SyntaxElement::Token(_) => contains_newline(child),
+                    SyntaxElement::Node(node) => self.nested_multiline(node),
+                })
+            }
+            SyntaxElement::Node(_) => false,
+        })
+    }
+
+    fn nested_multiline(&self, node: &SyntaxNode) -> bool {
+        match node.kind {
+            SyntaxKind::Function => self.is_multiline(node),
+            _ => node.children.iter().any(|child| match child {
+                SyntaxElement::Node(node) => self.nested_multiline(node),
+                SyntaxElement::Token(_) => false,
+            }),
+        }
+    }
+}
+
+fn contains_newline(element: &SyntaxElement) -> bool {
+    match element {
+        SyntaxElement::Token(token) => match token.token_type {
+            TokenType::Whitespace | TokenType::HardLinebreak => token.text.contains('\n'),
+            TokenType::Comment => token.text.starts_with("%%"),
+            _ => false,
+        },
+        SyntaxElement::Node(_) => false,
+    }
+}
+
+fn leading_newline(elements: &[SyntaxElement]) -> bool {
+    elements
+        .iter()
+        .take_while(
+            |e| matches!(e, SyntaxElement::Token(t) if t.token_type == TokenType::Whitespace),
+        )
+        .any(contains_newline)
+}
+
+fn trailing_newline(elements: &[SyntaxElement]) -> bool {
+    elements
+        .iter()
+        .rev()
+        .take_while(|e| {
+            matches!(e, SyntaxElement::Token(t) if t.token_type == TokenType::Whitespace
+                || t.text.starts_with("%%"))
+        })
+        .any(contains_newline)
+}
+
+// NOTE: comments before the first argument are otherwise parsed as part of the attributes
+fn starts_with_comment(atoms: &[Atom]) -> bool {
+    match atoms.first() {
+        Some(Atom::LineComment(_)) => true,
+        Some(Atom::Text(text)) => text.starts_with("%{"),
+        _ => false,
+    }
+}
+
+fn trim(mut atoms: Vec<Atom>) -> Vec<Atom> {
+    if atoms.last() == Some(&Atom::Space) {
+        atoms.pop();
+    }
+    if atoms.first() == Some(&Atom::Space) {
+        atoms.remove(0);
+    }
+    atoms
+}
+
+fn column(output: &str) -> usize {
+    output
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+}
+
+fn at_line_start(output: &str) -> bool {
+    output
+        .rsplit('\n')
+        .next()
+        .is_none_or(|line| line.chars().all(|c| c == ' '))
+}
+
+fn newline(output: &mut String, indent: usize) {
+    output.truncate(output.trim_end_matches(' ').len());
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&" ".repeat(indent));
+}
+
+fn separate(output: &mut String, indent: usize) {
+    if output.ends_with('\n') {
+        output.push_str(&" ".repeat(indent));
+    } else {
+        output.push(' ');
+    }
+}
+
+// NOTE: returns whether whitespace was left pending at the end of the atoms
+fn layout(atoms: &[Atom], output: &mut String, indent: usize, width: Option<usize>) -> bool {
+    let mut pending = false;
+    // NOTE: whitespace after a leading line comment is kept, it is part of the content
+    let mut leading = true;
+    let mut indented = true;
+
+    for atom in atoms {
+        if !indented && *atom != Atom::Space {
+            output.push_str(&" ".repeat(indent));
+            indented = true;
+        }
+
+        match atom {
+            Atom::Space => pending = true,
+            Atom::Text(text) if pending && leading && at_line_start(output) => {
+                output.push(' ');
+                output.push_str(text);
+                pending = false;
+                leading = false;
+            }
+            Atom::Text(text) => {
+                if pending && !at_line_start(output) {
+                    let word = text.split('\n').next().map_or(0, |w| w.chars().count());
+                    match width {
+                        Some(width) if column(output) + 1 + word > width => newline(output, indent),
+                        _ => output.push(' '),
+                    }
+                }
+                output.push_str(text);
+                pending = false;
+                leading = false;
+            }
+            Atom::LineComment(comment) => {
+                if pending && !at_line_start(output) {
+                    output.push(' ');
+                }
+                output.push_str(comment);
+                if comment.ends_with('\n') {
+                    output.push_str(&" ".repeat(indent));
+                }
+                pending = false;
+            }
+            Atom::HardLinebreak => {
+                output.truncate(output.trim_end_matches(' ').len());
+                output.push_str("\n\n");
+                // NOTE: the indentation is only written once there is content on the next line
+                indented = false;
+                pending = false;
+                leading = false;
+            }
+        }
+    }
+
+    pending
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_paragraphs() {
+        let formatter = Formatter::new().with_width(20);
+
+        assert_eq!(
+            formatter
+                .format("This   is a long paragraph\nwith [#b bold] text.\n\n\nNext")
+                .unwrap(),
+            "This is a long\nparagraph with\n[#b bold] text.\n\nNext\n"
+        );
+    }
+
+    #[test]
+    fn normalise_attributes() {
+        assert_eq!(
+            Formatter::new()
+                .format("[#table   @cols(2)@header  |  a|b |c ]")
+                .unwrap(),
+            "[#table @cols(2) @header a | b | c]\n"
+        );
+    }
+
+    #[test]
+    fn multiline_table() {
+        assert_eq!(
+            Formatter::new()
+                .format("[#table @cols(2)\n  | a |  b\n| c\n  | d\n   ]")
+                .unwrap(),
+            "[#table @cols(2)\n| a | b\n| c\n| d\n]\n"
+        );
+    }
+
+    #[test]
+    fn nested_multiline() {
+        assert_eq!(
+            Formatter::new()
+                .format("[#list first | [#table\n| a | b\n] | %% note\nlast]")
+                .unwrap(),
+            "[#list\n| first | [#table\n  | a | b\n  ] | %% note\n  last\n]\n"
+        );
+    }
+
+    #[test]
+    fn keep_empty_arguments() {
+        assert_eq!(
+            Formatter::new().format("[#b   |  | x |  ]").unwrap(),
+            "[#b | | x | ]\n"
+        );
+        assert_eq!(Formatter::new().format("[#b x |]").unwrap(), "[#b x]\n");
+    }
+
+    #[test]
+    fn keep_raw_and_comments() {
+        assert_eq!(
+            Formatter::new()
+                .format("a %{ keep   this }%   ```raw   text```\n%% line\nb")
+                .unwrap(),
+            "a %{ keep   this }% ```raw   text``` %% line\nb\n"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_input() {
+        assert!(Formatter::new().format("[#b unclosed").is_err());
+    }
+}
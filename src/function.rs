@@ -1,6 +1,11 @@
 use crate::{
-    argument::Argument, attribute::Attrs, error::Result, evaluator::Evaluator,
-    parse_tree::ParsedElement, return_value::ReturnValue,
+    argument::Argument,
+    attribute::{Attrs, FromAttrs},
+    error::Result,
+    evaluator::Evaluator,
+    metadata::Signature,
+    parse_tree::ParsedElement,
+    return_value::ReturnValue,
 };
 
 pub type Function<Context, Value> = Box<
@@ -14,119 +19,115 @@ pub type Function<Context, Value> = Box<
 
 pub trait ToFunction<'context, Context, Value, Args, Return> {
     fn to_function(self) -> Function<Context, Value>;
-}
-
-impl<'context, A, Context, Value, Func, Return> ToFunction<'context, Context, Value, (A,), Return>
-    for Func
-where
-    A: for<'a> Argument<'a, Context, Value>,
-    Func: Fn(&mut Context, Attrs, A) -> Return + 'static,
-    Return: ReturnValue<Value>,
-{
-    fn to_function(self) -> Function<Context, Value> {
-        Box::new(move |evaluator, context, attrs, args| {
-            let mut args = args.into_iter();
-
-            let arg = A::from_elements(evaluator, context, &mut args)?;
-
-            let result = self(context, attrs, arg);
-            Return::to_result_of_option(result)
-        })
-    }
-}
 
-impl<'context, A, B, Context, Value, Func, Return>
-    ToFunction<'context, Context, Value, (A, B), Return> for Func
-where
-    A: for<'a> Argument<'a, Context, Value>,
-    B: for<'a> Argument<'a, Context, Value>,
-    Func: Fn(&mut Context, Attrs, A, B) -> Return + 'static,
-    Return: ReturnValue<Value>,
-{
-    fn to_function(self) -> Function<Context, Value> {
-        Box::new(move |evaluator, context, attrs, args| {
-            let mut args = args.into_iter();
-
-            let arg1 = A::from_elements(evaluator, context, &mut args)?;
-            let arg2 = B::from_elements(evaluator, context, &mut args)?;
-
-            let result = self(context, attrs, arg1, arg2);
-            Return::to_result_of_option(result)
-        })
-    }
+    fn signature() -> Signature;
 }
 
-impl<'context, A, B, C, Context, Value, Func, Return>
-    ToFunction<'context, Context, Value, (A, B, C), Return> for Func
-where
-    A: for<'a> Argument<'a, Context, Value>,
-    B: for<'a> Argument<'a, Context, Value>,
-    C: for<'a> Argument<'a, Context, Value>,
-    Func: Fn(&mut Context, Attrs, A, B, C) -> Return + 'static,
-    Return: ReturnValue<Value>,
-{
-    fn to_function(self) -> Function<Context, Value> {
-        Box::new(move |evaluator, context, attrs, args| {
-            let mut args = args.into_iter();
-
-            let arg1 = A::from_elements(evaluator, context, &mut args)?;
-            let arg2 = B::from_elements(evaluator, context, &mut args)?;
-            let arg3 = C::from_elements(evaluator, context, &mut args)?;
-
-            let result = self(context, attrs, arg1, arg2, arg3);
-            Return::to_result_of_option(result)
-        })
-    }
+// NOTE: marks the argument list of functions that do not take attributes, attributes passed to
+// them are ignored
+pub struct WithoutAttrs;
+
+macro_rules! impl_to_function {
+    ($($arg:ident),*) => {
+        impl<'context, Attributes, $($arg,)* Context, Value, Func, Return>
+            ToFunction<'context, Context, Value, (Attributes, $($arg,)*), Return> for Func
+        where
+            $($arg: for<'a> Argument<'a, Context, Value>,)*
+            Func: Fn(&mut Context, Attributes, $($arg),*) -> Return + 'static,
+            Attributes: FromAttrs,
+            Return: ReturnValue<Value>,
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn to_function(self) -> Function<Context, Value> {
+                Box::new(move |evaluator, context, attrs, args| {
+                    let attrs = Attributes::from_attrs(attrs)?;
+                    let mut args = args.into_iter();
+
+                    $(let $arg = $arg::from_elements(evaluator, context, &mut args)?;)*
+
+                    let result = self(context, attrs, $($arg),*);
+                    Return::to_result_of_option(result)
+                })
+            }
+
+            fn signature() -> Signature {
+                Signature {
+                    attributes: Attributes::attributes(),
+                    arguments: vec![$($arg::metadata()),*],
+                    returns: Return::metadata(),
+                }
+            }
+        }
+
+        // NOTE: functions that take the attributes themselves borrow them from the input
+        impl<'context, $($arg,)* Context, Value, Func, Return>
+            ToFunction<'context, Context, Value, (Attrs<'static>, $($arg,)*), Return> for Func
+        where
+            $($arg: for<'a> Argument<'a, Context, Value>,)*
+            Func: for<'a> Fn(&mut Context, Attrs<'a>, $($arg),*) -> Return + 'static,
+            Return: ReturnValue<Value>,
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn to_function(self) -> Function<Context, Value> {
+                Box::new(move |evaluator, context, attrs, args| {
+                    let mut args = args.into_iter();
+
+                    $(let $arg = $arg::from_elements(evaluator, context, &mut args)?;)*
+
+                    let result = self(context, attrs, $($arg),*);
+                    Return::to_result_of_option(result)
+                })
+            }
+
+            fn signature() -> Signature {
+                Signature {
+                    attributes: vec![],
+                    arguments: vec![$($arg::metadata()),*],
+                    returns: Return::metadata(),
+                }
+            }
+        }
+
+        impl<'context, $($arg,)* Context, Value, Func, Return>
+            ToFunction<'context, Context, Value, (WithoutAttrs, $($arg,)*), Return> for Func
+        where
+            $($arg: for<'a> Argument<'a, Context, Value>,)*
+            Func: Fn(&mut Context, $($arg),*) -> Return + 'static,
+            Return: ReturnValue<Value>,
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn to_function(self) -> Function<Context, Value> {
+                Box::new(move |evaluator, context, _attrs, args| {
+                    let mut args = args.into_iter();
+
+                    $(let $arg = $arg::from_elements(evaluator, context, &mut args)?;)*
+
+                    let result = self(context, $($arg),*);
+                    Return::to_result_of_option(result)
+                })
+            }
+
+            fn signature() -> Signature {
+                Signature {
+                    attributes: vec![],
+                    arguments: vec![$($arg::metadata()),*],
+                    returns: Return::metadata(),
+                }
+            }
+        }
+    };
 }
 
-impl<'context, A, B, C, D, Context, Value, Func, Return>
-    ToFunction<'context, Context, Value, (A, B, C, D), Return> for Func
-where
-    A: for<'a> Argument<'a, Context, Value>,
-    B: for<'a> Argument<'a, Context, Value>,
-    C: for<'a> Argument<'a, Context, Value>,
-    D: for<'a> Argument<'a, Context, Value>,
-    Func: Fn(&mut Context, Attrs, A, B, C, D) -> Return + 'static,
-    Return: ReturnValue<Value>,
-{
-    fn to_function(self) -> Function<Context, Value> {
-        Box::new(move |evaluator, context, attrs, args| {
-            let mut args = args.into_iter();
-
-            let arg1 = A::from_elements(evaluator, context, &mut args)?;
-            let arg2 = B::from_elements(evaluator, context, &mut args)?;
-            let arg3 = C::from_elements(evaluator, context, &mut args)?;
-            let arg4 = D::from_elements(evaluator, context, &mut args)?;
-
-            let result = self(context, attrs, arg1, arg2, arg3, arg4);
-            Return::to_result_of_option(result)
-        })
-    }
-}
-
-impl<'context, A, B, C, D, E, Context, Value, Func, Return>
-    ToFunction<'context, Context, Value, (A, B, C, D, E), Return> for Func
-where
-    A: for<'a> Argument<'a, Context, Value>,
-    B: for<'a> Argument<'a, Context, Value>,
-    C: for<'a> Argument<'a, Context, Value>,
-    D: for<'a> Argument<'a, Context, Value>,
-    E: for<'a> Argument<'a, Context, Value>,
-    Func: Fn(&mut Context, Attrs, A, B, C, D, E) -> Return + 'static,
-    Return: ReturnValue<Value>,
-{
-    fn to_function(self) -> Function<Context, Value> {
-        Box::new(move |evaluator, context, attrs, args| {
-            let mut args = args.into_iter();
-
-            let arg1 = A::from_elements(evaluator, context, &mut args)?;
-            let arg2 = B::from_elements(evaluator, context, &mut args)?;
-            let arg3 = C::from_elements(evaluator, context, &mut args)?;
-            let arg4 = D::from_elements(evaluator, context, &mut args)?;
-            let arg5 = E::from_elements(evaluator, context, &mut args)?;
-
-            let result = self(context, attrs, arg1, arg2, arg3, arg4, arg5);
-            Return::to_result_of_option(result)
-        })
-    }
-}
+impl_to_function!();
+impl_to_function!(A);
+impl_to_function!(A, B);
+impl_to_function!(A, B, C);
+impl_to_function!(A, B, C, D);
+impl_to_function!(A, B, C, D, E);
+impl_to_function!(A, B, C, D, E, F);
+impl_to_function!(A, B, C, D, E, F, G);
+impl_to_function!(A, B, C, D, E, F, G, H);
+impl_to_function!(A, B, C, D, E, F, G, H, I);
+impl_to_function!(A, B, C, D, E, F, G, H, I, J);
+impl_to_function!(A, B, C, D, E, F, G, H, I, J, K);
+impl_to_function!(A, B, C, D, E, F, G, H, I, J, K, L);
@@ -14,10 +14,13 @@ pub enum TokenType {
     AttributeIdentifier,
     FunctionIdentifier,
     ArgumentSeparator,
+    Escape,
+    Comment,
+    Raw,
     Error,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub span: Span,
@@ -29,21 +32,33 @@ impl Token {
     }
 }
 
+#[derive(Clone)]
 pub struct Lexer<'input> {
     chars: Chars<'input>,
     start: usize,
     current: usize,
+    keep_comments: bool,
+    after_line_comment: bool,
 }
 
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
+        let offset = input.len() - input.trim_start().len();
+
         Self {
             chars: input.trim().chars(),
-            start: 0,
-            current: 0,
+            start: offset,
+            current: offset,
+            keep_comments: false,
+            after_line_comment: false,
         }
     }
 
+    pub fn with_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
     fn token(&mut self, token_type: TokenType) -> Token {
         let span = self.start..self.current;
         self.start = self.current;
@@ -70,30 +85,118 @@ impl<'input> Lexer<'input> {
     }
 
     fn identifier(&mut self, token_type: TokenType) -> Token {
-        let is_valid_char = |c: char| {
-            c.is_ascii_lowercase() || c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-'
-        };
+        let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-';
 
         loop {
             match self.peek() {
                 Some(c) if is_valid_char(c) => {
                     self.consume();
                 }
+                // NOTE: function identifiers can contain the '.' that separates namespaces, but
+                // only between two non-empty segments
+                Some('.')
+                    if token_type == TokenType::FunctionIdentifier
+                        && self.current - self.start > 1
+                        && self.peek_next().is_some_and(is_valid_char) =>
+                {
+                    self.consume();
+                }
                 _ => break,
             }
         }
 
+        if self.current - self.start == 1 {
+            return self.token(TokenType::Error);
+        }
+
         self.token(token_type)
     }
 
+    pub fn is_reserved(c: char) -> bool {
+        ['[', ']', '(', ')', '|', '#', '@'].contains(&c)
+    }
+
+    pub fn is_escapable(c: char) -> bool {
+        Self::is_reserved(c) || c == '%' || c == '`'
+    }
+
+    fn is_escape(&mut self) -> bool {
+        self.peek() == Some('\\') && self.peek_next().is_some_and(Self::is_escapable)
+    }
+
+    fn is_comment_start(&mut self) -> bool {
+        self.peek() == Some('%') && matches!(self.peek_next(), Some('%' | '{'))
+    }
+
+    fn is_raw_fence(&mut self) -> bool {
+        self.chars.clone().take(3).filter(|c| *c == '`').count() == 3
+    }
+
+    fn backticks(&mut self) -> usize {
+        let mut count = 0;
+        while self.peek() == Some('`') {
+            self.consume();
+            count += 1;
+        }
+        count
+    }
+
+    fn raw(&mut self) -> Token {
+        // NOTE: the first backtick is already consumed, the content ends at the next run with the
+        // exact same amount of backticks
+        let fence = self.backticks() + 1;
+
+        loop {
+            match self.peek() {
+                None => return self.token(TokenType::Error),
+                Some('`') => {
+                    if self.backticks() == fence {
+                        return self.token(TokenType::Raw);
+                    }
+                }
+                Some(_) => {
+                    self.consume();
+                }
+            }
+        }
+    }
+
+    fn line_comment(&mut self) -> Token {
+        while let Some(c) = self.consume() {
+            if c == '\n' {
+                break;
+            }
+        }
+
+        self.after_line_comment = true;
+        self.token(TokenType::Comment)
+    }
+
+    fn block_comment(&mut self) -> Token {
+        self.consume();
+
+        loop {
+            match self.consume() {
+                None => return self.token(TokenType::Error),
+                Some('}') if self.peek() == Some('%') => {
+                    self.consume();
+                    return self.token(TokenType::Comment);
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn text(&mut self) -> Token {
-        let is_invalid_char =
-            |c: char| ['[', ']', '(', ')', '|', '#', '@'].contains(&c) || Self::is_whitespace(c);
+        let is_invalid_char = |c: char| Self::is_reserved(c) || Self::is_whitespace(c);
 
         loop {
             match self.peek() {
                 None => break,
                 Some(c) if is_invalid_char(c) => break,
+                Some('\\') if self.is_escape() => break,
+                Some('%') if self.is_comment_start() => break,
+                Some('`') if self.is_raw_fence() => break,
                 _ => {
                     self.consume();
                 }
@@ -122,10 +225,11 @@ impl<'input> Lexer<'input> {
     }
 }
 
-impl<'input> Iterator for Lexer<'input> {
-    type Item = Token;
+impl<'input> Lexer<'input> {
+    fn next_token(&mut self) -> Option<Token> {
+        // NOTE: a line comment consumes its newline, so a newline directly after it ends a paragraph
+        let after_line_comment = std::mem::take(&mut self.after_line_comment);
 
-    fn next(&mut self) -> Option<Self::Item> {
         self.consume().map(|curr| match curr {
             '[' => self.token(TokenType::LeftBracket),
             ']' => self.token(TokenType::RightBracket),
@@ -134,12 +238,75 @@ impl<'input> Iterator for Lexer<'input> {
             '|' => self.token(TokenType::ArgumentSeparator),
             '#' => self.identifier(TokenType::FunctionIdentifier),
             '@' => self.identifier(TokenType::AttributeIdentifier),
+            '`' if self.peek() == Some('`') && self.peek_next() == Some('`') => self.raw(),
+            '%' if self.peek() == Some('%') => self.line_comment(),
+            '%' if self.peek() == Some('{') => self.block_comment(),
             '\n' if matches!(self.peek(), Some('\n')) => {
                 self.consume();
                 self.token(TokenType::HardLinebreak)
             }
+            '\n' if after_line_comment => self.token(TokenType::HardLinebreak),
+            '\\' if self.peek().is_some_and(Self::is_escapable) => {
+                self.consume();
+                self.token(TokenType::Escape)
+            }
             ' ' | '\t' | '\n' | '\r' => self.whitespace(),
             _ => self.text(),
         })
     }
 }
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = self.next_token()?;
+            if token.token_type != TokenType::Comment || self.keep_comments {
+                return Some(token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<(TokenType, &str)> {
+        Lexer::new(input)
+            .map(|token| (token.token_type, &input[token.span]))
+            .collect()
+    }
+
+    #[test]
+    fn namespaced_identifiers() {
+        assert_eq!(
+            tokens("#math.frac @a.b"),
+            vec![
+                (TokenType::FunctionIdentifier, "#math.frac"),
+                (TokenType::Whitespace, " "),
+                (TokenType::AttributeIdentifier, "@a"),
+                (TokenType::Text, ".b"),
+            ]
+        );
+        assert_eq!(
+            tokens("#b."),
+            vec![
+                (TokenType::FunctionIdentifier, "#b"),
+                (TokenType::Text, "."),
+            ]
+        );
+        assert_eq!(
+            tokens("#.b"),
+            vec![(TokenType::Error, "#"), (TokenType::Text, ".b")]
+        );
+        assert_eq!(
+            tokens("#a..b"),
+            vec![
+                (TokenType::FunctionIdentifier, "#a"),
+                (TokenType::Text, "..b"),
+            ]
+        );
+    }
+}
@@ -1,13 +1,29 @@
 pub mod argument;
 pub mod attribute;
 pub mod context;
+pub mod control_flow;
+pub mod defaulted;
+pub mod definition;
+pub mod diagnostic;
 pub mod error;
 pub mod evaluator;
+pub mod format;
 pub mod function;
 pub mod lexer;
+pub mod library;
+pub mod metadata;
 pub mod parse_tree;
 pub mod parser;
+pub mod reference;
 pub mod registry;
 pub mod return_value;
+pub mod scope;
+#[cfg(feature = "std-lib")]
+pub mod std_lib;
+pub mod syntax;
 pub mod value;
 pub mod variadic;
+pub mod writer;
+
+#[cfg(feature = "macros")]
+pub use noet_macros::{function, Context, FromAttrs};
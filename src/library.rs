@@ -0,0 +1,14 @@
+use crate::registry::FunctionRegistry;
+
+pub trait FunctionLibrary<Context, Value> {
+    fn register_functions(&self, registry: &mut FunctionRegistry<Context, Value>);
+}
+
+impl<Context, Value, F> FunctionLibrary<Context, Value> for F
+where
+    F: Fn(&mut FunctionRegistry<Context, Value>),
+{
+    fn register_functions(&self, registry: &mut FunctionRegistry<Context, Value>) {
+        self(registry)
+    }
+}
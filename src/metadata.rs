@@ -0,0 +1,133 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionMetadata {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub docs: Option<String>,
+    pub attributes: Vec<AttributeMetadata>,
+    pub arguments: Vec<ArgumentMetadata>,
+    pub returns: ReturnMetadata,
+    pub ignore_surplus_arguments: bool,
+    pub raw: bool,
+}
+
+impl FunctionMetadata {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            aliases: vec![],
+            docs: None,
+            attributes: vec![],
+            arguments: vec![],
+            returns: ReturnMetadata::new(ReturnKind::Nothing, false),
+            ignore_surplus_arguments: false,
+            raw: false,
+        }
+    }
+
+    pub fn max_arguments(&self) -> Option<usize> {
+        match self.arguments.iter().any(|argument| argument.variadic) {
+            true => None,
+            false => Some(self.arguments.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeMetadata {
+    pub key: String,
+    pub docs: Option<String>,
+    pub value_type: Option<String>,
+    pub required: bool,
+}
+
+impl AttributeMetadata {
+    pub fn new(key: impl Into<String>, docs: Option<&str>) -> Self {
+        Self {
+            key: key.into(),
+            docs: docs.map(str::to_string),
+            value_type: None,
+            required: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentMetadata {
+    pub type_name: String,
+    pub variadic: bool,
+    pub optional: bool,
+}
+
+impl ArgumentMetadata {
+    pub fn new(type_name: &str) -> Self {
+        Self {
+            type_name: short_type_name(type_name),
+            variadic: false,
+            optional: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReturnKind {
+    Nothing,
+    Value,
+    OptionalValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnMetadata {
+    pub kind: ReturnKind,
+    pub fallible: bool,
+}
+
+impl ReturnMetadata {
+    pub fn new(kind: ReturnKind, fallible: bool) -> Self {
+        Self { kind, fallible }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub attributes: Vec<AttributeMetadata>,
+    pub arguments: Vec<ArgumentMetadata>,
+    pub returns: ReturnMetadata,
+}
+
+// NOTE: strips the module paths from a type name, 'alloc::vec::Vec<noet::Foo>' becomes 'Vec<Foo>'
+pub fn short_type_name(type_name: &str) -> String {
+    let mut short = String::new();
+    let mut path_start = 0;
+    let mut chars = type_name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            short.truncate(path_start);
+            continue;
+        }
+
+        short.push(c);
+        if !c.is_alphanumeric() && c != '_' {
+            path_start = short.len();
+        }
+    }
+
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_module_paths() {
+        assert_eq!(short_type_name("u32"), "u32");
+        assert_eq!(short_type_name("alloc::string::String"), "String");
+        assert_eq!(
+            short_type_name("noet::variadic::Variadic<core::option::Option<&str>>"),
+            "Variadic<Option<&str>>"
+        );
+        assert_eq!(short_type_name("(a::B, c::D)"), "(B, D)");
+    }
+}
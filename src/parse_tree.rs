@@ -1,13 +1,76 @@
-use crate::attribute::Attribute;
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter},
+};
 
-#[derive(Debug, PartialEq)]
+use crate::{attribute::Attribute, lexer::Span, writer::write_source_lossy};
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParsedElement<'input> {
-    Text(&'input str),
+    Text(Cow<'input, str>, Span),
     Function(
-        &'input str,
+        Cow<'input, str>,
+        Span,
         Vec<Attribute<'input>>,
         Vec<ParsedElement<'input>>,
+        Span,
     ),
-    HardLinebreak(),
-    Block(Vec<ParsedElement<'input>>),
+    HardLinebreak(Span),
+    Block(Vec<ParsedElement<'input>>, Span),
+    Group(Vec<ParsedElement<'input>>, Span),
+    Comment(Cow<'input, str>, Span),
+    Error(Span),
+}
+
+impl<'input> ParsedElement<'input> {
+    pub fn span(&self) -> &Span {
+        match self {
+            ParsedElement::Text(_, span)
+            | ParsedElement::Function(_, _, _, _, span)
+            | ParsedElement::HardLinebreak(span)
+            | ParsedElement::Block(_, span)
+            | ParsedElement::Group(_, span)
+            | ParsedElement::Comment(_, span)
+            | ParsedElement::Error(span) => span,
+        }
+    }
+
+    pub fn into_owned(self) -> ParsedElement<'static> {
+        match self {
+            ParsedElement::Text(text, span) => ParsedElement::Text(text.into_owned().into(), span),
+            ParsedElement::Function(name, name_span, attrs, args, span) => ParsedElement::Function(
+                name.into_owned().into(),
+                name_span,
+                attrs.into_iter().map(Attribute::into_owned).collect(),
+                args.into_iter().map(ParsedElement::into_owned).collect(),
+                span,
+            ),
+            ParsedElement::HardLinebreak(span) => ParsedElement::HardLinebreak(span),
+            ParsedElement::Block(elements, span) => ParsedElement::Block(
+                elements
+                    .into_iter()
+                    .map(ParsedElement::into_owned)
+                    .collect(),
+                span,
+            ),
+            ParsedElement::Group(elements, span) => ParsedElement::Group(
+                elements
+                    .into_iter()
+                    .map(ParsedElement::into_owned)
+                    .collect(),
+                span,
+            ),
+            ParsedElement::Comment(comment, span) => {
+                ParsedElement::Comment(comment.into_owned().into(), span)
+            }
+            ParsedElement::Error(span) => ParsedElement::Error(span),
+        }
+    }
+}
+
+impl<'input> Display for ParsedElement<'input> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_source_lossy(f, std::slice::from_ref(self))
+    }
 }
@@ -1,4 +1,4 @@
-use std::iter::Peekable;
+use std::{borrow::Cow, iter::Peekable};
 
 use crate::{
     attribute::Attribute,
@@ -7,11 +7,16 @@ use crate::{
     parse_tree::ParsedElement,
 };
 
+pub(crate) const MAX_NESTING_DEPTH: usize = 128;
+
 pub struct Parser<'input> {
     input: &'input str,
     tokens: Peekable<Lexer<'input>>,
-    start: usize,
     current: usize,
+    previous: Option<TokenType>,
+    depth: usize,
+    recover: bool,
+    errors: Vec<Error>,
 }
 
 impl<'input> Parser<'input> {
@@ -19,16 +24,34 @@ impl<'input> Parser<'input> {
         Self {
             input,
             tokens: Lexer::new(input).peekable(),
-            start: 0,
             current: 0,
+            previous: None,
+            depth: 0,
+            recover: false,
+            errors: vec![],
         }
     }
 
+    pub fn with_comments(mut self) -> Self {
+        self.tokens = Lexer::new(self.input).with_comments().peekable();
+        self
+    }
+
+    pub fn with_error_recovery(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
     #[inline]
     fn consume(&mut self) -> Option<Token> {
         let result = self.tokens.next();
         if let Some(res) = &result {
             self.current = res.span.end;
+            self.previous = Some(res.token_type);
         }
 
         result
@@ -36,10 +59,11 @@ impl<'input> Parser<'input> {
 
     #[inline]
     fn consume_expect(&mut self, token_type: TokenType) -> Result<Token> {
-        let token = self.consume();
-
-        let Some(token) = token else {
-            return Err(Error::Parse("Reached EOF".to_string(), None));
+        let Some(token) = self.tokens.peek() else {
+            return Err(Error::Parse(
+                "Reached EOF".to_string(),
+                Some(self.current..self.current),
+            ));
         };
 
         if token.token_type != token_type {
@@ -48,11 +72,12 @@ impl<'input> Parser<'input> {
                     "Expected token {:?} but got {:?}",
                     token_type, token.token_type
                 ),
-                Some(token.span),
+                Some(token.span.clone()),
             ));
         }
 
-        Ok(token)
+        // NOTE: unwrapping here is allowed as we just peeked the token
+        Ok(self.consume().unwrap())
     }
 
     #[inline]
@@ -63,6 +88,23 @@ impl<'input> Parser<'input> {
         }
     }
 
+    // NOTE: comments between attributes have no place in the tree, so they are skipped like
+    // whitespace, which keeps the same documents valid with and without comments
+    fn skip_comments_before(&mut self, token_types: &[TokenType]) {
+        let is_trivia =
+            |token_type| matches!(token_type, TokenType::Whitespace | TokenType::Comment);
+        let next = self
+            .tokens
+            .clone()
+            .find(|token| !is_trivia(token.token_type));
+
+        if next.is_some_and(|token| token_types.contains(&token.token_type)) {
+            while self.peek_type().is_some_and(is_trivia) {
+                self.consume();
+            }
+        }
+    }
+
     #[inline]
     fn peek_type(&mut self) -> Option<TokenType> {
         self.tokens.peek().map(|t| t.token_type)
@@ -74,85 +116,138 @@ impl<'input> Parser<'input> {
     }
 
     #[inline]
-    fn start_span(&mut self) {
-        self.start = self.current;
+    fn start_span(&mut self) -> usize {
+        self.peek_span().map_or(self.current, |s| s.start)
     }
 
     #[inline]
-    fn get_span(&mut self) -> Span {
-        self.start..self.current
+    fn get_span(&mut self, start: usize) -> Span {
+        start..self.current
     }
 
     #[inline]
-    fn text(&mut self, start_with_paren: bool) -> ParsedElement<'input> {
-        let mut paren_depth = start_with_paren as u32;
+    fn text_value(&mut self) -> (Cow<'input, str>, Span) {
+        let start = self.start_span();
+        let mut end = start;
+        let mut unescaped: Option<String> = None;
+        let mut paren_depth = 0u32;
 
         loop {
             match self.peek_type() {
-                Some(TokenType::Text | TokenType::Whitespace) => {
-                    self.consume();
-                }
-                Some(TokenType::LeftParen) => {
-                    self.consume();
-                    paren_depth += 1;
-                }
-                Some(TokenType::RightParen) if paren_depth > 0 => {
-                    self.consume();
-                    paren_depth -= 1;
-                }
-                // TODO: add support for parsing square brackets without function identifier
+                Some(TokenType::Text | TokenType::Whitespace | TokenType::Escape) => {}
+                Some(TokenType::LeftParen) => paren_depth += 1,
+                Some(TokenType::RightParen) if paren_depth > 0 => paren_depth -= 1,
                 _ => break,
             }
+
+            // NOTE: unwrapping here is allowed as we first check the next token type before consuming
+            let token = self.consume().unwrap();
+            let escaped = token.token_type == TokenType::Escape;
+
+            // NOTE: a gap between two tokens means the lexer dropped a comment in between
+            if unescaped.is_none() && (escaped || token.span.start != end) {
+                unescaped = Some(self.input[start..end].to_string());
+            }
+
+            if let Some(text) = &mut unescaped {
+                let text_start = token.span.start + escaped as usize;
+                text.push_str(&self.input[text_start..token.span.end]);
+            }
+
+            end = token.span.end;
+        }
+
+        let span = start..end;
+        match unescaped {
+            Some(text) => (Cow::Owned(text), span),
+            None => (Cow::Borrowed(&self.input[span.clone()]), span),
         }
+    }
+
+    #[inline]
+    fn raw_value(&mut self) -> Result<(Cow<'input, str>, Span)> {
+        let token = self.consume_expect(TokenType::Raw)?;
+
+        let source = &self.input[token.span.clone()];
+        let fence = source.len() - source.trim_start_matches('`').len();
+        let mut span = token.span.start + fence..token.span.end - fence;
+
+        // NOTE: raw text cannot start or end with a backtick, as it would be part of the fence,
+        // so a space between the fence and a backtick is padding
+        let content = &self.input[span.clone()];
+        if content.starts_with(' ') && content.trim_start_matches(' ').starts_with('`') {
+            span.start += 1;
+        }
+        if content.ends_with(' ') && content.trim_end_matches(' ').ends_with('`') {
+            span.end -= 1;
+        }
+
+        Ok((Cow::Borrowed(&self.input[span.clone()]), span))
+    }
 
-        let span = self.get_span();
-        ParsedElement::Text(&self.input[span])
+    #[inline]
+    fn text(&mut self) -> ParsedElement<'input> {
+        let (text, span) = self.text_value();
+        ParsedElement::Text(text, span)
+    }
+
+    #[inline]
+    fn identifier(&mut self, token_type: TokenType) -> Result<(&'input str, Span)> {
+        let token = self.consume_expect(token_type)?;
+        let span = token.span.start + 1..token.span.end;
+
+        Ok((&self.input[span.clone()], span))
     }
 
     #[inline]
     fn attribute(&mut self) -> Result<Attribute<'input>> {
-        let key = self.consume_expect(TokenType::AttributeIdentifier)?;
-        let key_str = &self.input[key.span].trim_start_matches(|c| c == '@');
+        let (key, key_span) = self.identifier(TokenType::AttributeIdentifier)?;
 
         match self.peek_type() {
             Some(TokenType::Whitespace | TokenType::RightBracket) => {
-                Ok(Attribute::new_flag(key_str))
+                Ok(Attribute::new_flag(key, key_span))
             }
             Some(TokenType::LeftParen) => {
                 self.consume_expect(TokenType::LeftParen)?;
 
-                self.start_span();
-
-                let value_element = self.text(false);
+                let (value, value_span) = match self.peek_type() {
+                    Some(TokenType::Raw) => self.raw_value()?,
+                    _ => self.text_value(),
+                };
 
                 self.consume_expect(TokenType::RightParen)?;
 
-                if let ParsedElement::Text(value) = value_element {
-                    Ok(Attribute::new_value(key_str, value))
-                } else {
-                    Err(Error::Parse(
-                        "Value of attribute should be a string".to_string(),
-                        Some(self.get_span()),
-                    ))
-                }
+                Ok(Attribute::new_value(key, key_span, value, value_span))
             }
             x => Err(Error::Parse(
                 format!("Unexpected token while parsing attribute {x:?}"),
-                Some(self.get_span()),
+                Some(self.peek_span().unwrap_or(key_span)),
             )),
         }
     }
 
-    fn trim_argument(elements: &mut Vec<ParsedElement>) -> bool {
-        if let Some(ParsedElement::Text(t)) = elements.first_mut() {
-            *t = t.trim_start();
+    fn trim_text(text: &mut Cow<str>, trim: fn(&str) -> &str) -> usize {
+        let length = text.len();
+        match text {
+            Cow::Borrowed(t) => *t = trim(t),
+            Cow::Owned(t) => *t = trim(t).to_string(),
+        }
+
+        // NOTE: escape sequences never contain whitespace, so the amount of trimmed bytes is the
+        // same for the source text and the unescaped text
+        length - text.len()
+    }
+
+    fn trim_argument(elements: &mut Vec<ParsedElement>, trim_start: bool, trim_end: bool) -> bool {
+        if let Some(ParsedElement::Text(t, span)) = elements.first_mut().filter(|_| trim_start) {
+            span.start += Self::trim_text(t, str::trim_start);
             if t.is_empty() {
                 elements.remove(0);
             }
         }
 
-        if let Some(ParsedElement::Text(t)) = elements.last_mut() {
-            *t = t.trim_end();
+        if let Some(ParsedElement::Text(t, span)) = elements.last_mut().filter(|_| trim_end) {
+            span.end -= Self::trim_text(t, str::trim_end);
             if t.is_empty() {
                 elements.pop();
             }
@@ -161,11 +256,50 @@ impl<'input> Parser<'input> {
         !elements.is_empty()
     }
 
+    fn bracketed(&mut self, start: usize) -> Result<ParsedElement<'input>> {
+        // NOTE: limiting the nesting depth prevents deeply nested input from overflowing the stack
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(Error::Parse(
+                format!(
+                    "Square brackets can not be nested more than {MAX_NESTING_DEPTH} levels deep"
+                ),
+                Some(start..self.current),
+            ));
+        }
+
+        self.depth += 1;
+        let result = match self.peek_type() {
+            Some(TokenType::FunctionIdentifier) => self.function(start),
+            _ => self.group(start),
+        };
+        self.depth -= 1;
+
+        result
+    }
+
+    #[inline]
+    fn group(&mut self, start: usize) -> Result<ParsedElement<'input>> {
+        let elements = self.block()?;
+
+        if self.peek_type() != Some(TokenType::RightBracket) {
+            return Err(Error::Parse(
+                "Expected RightBracket at the end of bracket group".to_string(),
+                Some(self.peek_span().unwrap_or(self.current..self.current)),
+            ));
+        }
+
+        self.consume_expect(TokenType::RightBracket)?;
+
+        Ok(ParsedElement::Group(elements, self.get_span(start)))
+    }
+
     #[inline]
-    fn function(&mut self) -> Result<ParsedElement<'input>> {
-        let identifier = self.consume_expect(TokenType::FunctionIdentifier)?;
+    fn function(&mut self, start: usize) -> Result<ParsedElement<'input>> {
+        let (name, name_span) = self.identifier(TokenType::FunctionIdentifier)?;
 
+        let trivia_before = [TokenType::AttributeIdentifier, TokenType::ArgumentSeparator];
         self.skip_whitespace();
+        self.skip_comments_before(&trivia_before);
 
         let mut attributes = vec![];
         let mut arguments = vec![];
@@ -173,6 +307,7 @@ impl<'input> Parser<'input> {
         while self.peek_type() == Some(TokenType::AttributeIdentifier) {
             attributes.push(self.attribute()?);
             self.skip_whitespace();
+            self.skip_comments_before(&trivia_before);
         }
 
         if let Some(TokenType::ArgumentSeparator) = self.peek_type() {
@@ -180,13 +315,22 @@ impl<'input> Parser<'input> {
         }
 
         while self.peek_type() != Some(TokenType::RightBracket) {
+            let argument_start = self.start_span();
+
+            // NOTE: raw text is never trimmed, so it keeps covering the exact source slice
+            let trim_start = self.peek_type() != Some(TokenType::Raw);
             let mut argument = self.block()?;
-            Self::trim_argument(&mut argument);
+            let trim_end = self.previous != Some(TokenType::Raw);
+            Self::trim_argument(&mut argument, trim_start, trim_end);
 
             if argument.len() == 1 {
                 arguments.push(argument.remove(0));
             } else {
-                arguments.push(ParsedElement::Block(argument));
+                let span = match (argument.first(), argument.last()) {
+                    (Some(first), Some(last)) => first.span().start..last.span().end,
+                    _ => argument_start..argument_start,
+                };
+                arguments.push(ParsedElement::Block(argument, span));
             }
 
             if let Some(TokenType::ArgumentSeparator) = self.peek_type() {
@@ -194,19 +338,19 @@ impl<'input> Parser<'input> {
             } else if self.peek_type() != Some(TokenType::RightBracket) {
                 return Err(Error::Parse(
                     "Expected RightBracket at the end of function arguments".to_string(),
-                    self.peek_span(),
+                    Some(self.peek_span().unwrap_or(self.current..self.current)),
                 ));
             }
         }
 
         self.consume_expect(TokenType::RightBracket)?;
 
-        let _span = self.get_span();
-
         Ok(ParsedElement::Function(
-            self.input[identifier.span].trim_start_matches(|c| c == '#'),
+            name.into(),
+            name_span,
             attributes,
             arguments,
+            self.get_span(start),
         ))
     }
 
@@ -214,11 +358,7 @@ impl<'input> Parser<'input> {
     fn block(&mut self) -> Result<Vec<ParsedElement<'input>>> {
         let mut elements = vec![];
 
-        loop {
-            let Some(token_type) = self.peek_type() else {
-                 break;
-            };
-
+        while let Some(token_type) = self.peek_type() {
             match token_type {
                 TokenType::AttributeIdentifier
                 | TokenType::ArgumentSeparator
@@ -234,25 +374,129 @@ impl<'input> Parser<'input> {
     }
 
     fn element(&mut self) -> Option<Result<ParsedElement<'input>>> {
-        self.start_span();
+        let start = self.start_span();
 
-        let Some(token) = self.consume() else {
-            return None;
-        };
+        let checkpoint = (self.recover && self.peek_type() == Some(TokenType::LeftBracket))
+            .then(|| self.tokens.clone());
+
+        match self.parse_element(start)? {
+            Err(error) if self.recover => Some(Ok(self.recover(error, start, checkpoint))),
+            result => Some(result),
+        }
+    }
+
+    fn recover(
+        &mut self,
+        error: Error,
+        start: usize,
+        checkpoint: Option<Peekable<Lexer<'input>>>,
+    ) -> ParsedElement<'input> {
+        match checkpoint {
+            Some(tokens) if self.peek_type().is_none() => {
+                // NOTE: an unclosed function swallows the rest of the document, so we rewind and
+                // only mark its opening bracket and name as invalid, the rest is parsed again
+                self.tokens = tokens;
+                self.consume();
+                if self.peek_type() == Some(TokenType::FunctionIdentifier) {
+                    self.consume();
+                }
+
+                let end = self.current;
+                self.errors
+                    .retain(|e| e.span().is_none_or(|span| span.start < end));
+                self.errors.push(Error::Parse(
+                    "Function is missing a closing ']'".to_string(),
+                    Some(start..end),
+                ));
+            }
+            Some(_) => {
+                self.errors.push(error);
+
+                // NOTE: an argument separator of the enclosing function ends the invalid source,
+                // so the enclosing function keeps its other arguments
+                let mut depth = 0u32;
+                while let Some(token_type) = self.peek_type() {
+                    match token_type {
+                        TokenType::HardLinebreak => break,
+                        TokenType::ArgumentSeparator if depth == 0 && self.depth > 0 => break,
+                        TokenType::LeftBracket => depth += 1,
+                        TokenType::RightBracket if depth == 0 => {
+                            self.consume();
+                            break;
+                        }
+                        TokenType::RightBracket => depth -= 1,
+                        _ => {}
+                    }
+                    self.consume();
+                }
+            }
+            None => self.errors.push(error),
+        }
+
+        ParsedElement::Error(start..self.current)
+    }
 
-        match token.token_type {
-            TokenType::Text | TokenType::Whitespace => Some(Ok(self.text(false))),
-            TokenType::LeftParen => Some(Ok(self.text(true))),
-            TokenType::HardLinebreak => Some(Ok(ParsedElement::HardLinebreak())),
-            TokenType::LeftBracket => Some(self.function()),
-            TokenType::RightBracket => todo!(),
-            TokenType::RightParen => todo!(),
-            TokenType::AttributeIdentifier => todo!(),
-            TokenType::FunctionIdentifier => todo!(),
-            TokenType::ArgumentSeparator => todo!(),
-            TokenType::Error => todo!(),
+    fn parse_element(&mut self, start: usize) -> Option<Result<ParsedElement<'input>>> {
+        match self.peek_type()? {
+            TokenType::Text | TokenType::Whitespace | TokenType::Escape | TokenType::LeftParen => {
+                Some(Ok(self.text()))
+            }
+            TokenType::HardLinebreak => {
+                self.consume();
+                Some(Ok(ParsedElement::HardLinebreak(self.get_span(start))))
+            }
+            TokenType::Raw => Some(
+                self.raw_value()
+                    .map(|(text, span)| ParsedElement::Text(text, span)),
+            ),
+            TokenType::Comment => {
+                let span = self.consume()?.span;
+                Some(Ok(ParsedElement::Comment(
+                    self.input[span.clone()].into(),
+                    span,
+                )))
+            }
+            TokenType::LeftBracket => {
+                self.consume();
+                Some(self.bracketed(start))
+            }
+            TokenType::RightBracket => self.unexpected("there is no matching '['"),
+            TokenType::RightParen => self.unexpected("there is no matching '('"),
+            TokenType::AttributeIdentifier => {
+                self.unexpected("attributes are only allowed at the start of a function")
+            }
+            TokenType::FunctionIdentifier => {
+                self.unexpected("function identifiers should directly follow a '['")
+            }
+            TokenType::ArgumentSeparator => {
+                self.unexpected("argument separators are only allowed inside a function")
+            }
+            TokenType::Error if self.input[start..].starts_with("%{") => {
+                let span = self.consume()?.span;
+                Some(Err(Error::Parse(
+                    "Block comment is never closed with '}%'".to_string(),
+                    Some(span),
+                )))
+            }
+            TokenType::Error if self.input[start..].starts_with("```") => {
+                let span = self.consume()?.span;
+                Some(Err(Error::Parse(
+                    "Raw text is never closed with a matching backtick fence".to_string(),
+                    Some(span),
+                )))
+            }
+            TokenType::Error => self.unexpected("it should be followed by a name"),
         }
     }
+
+    fn unexpected(&mut self, reason: &str) -> Option<Result<ParsedElement<'input>>> {
+        let span = self.consume()?.span;
+
+        Some(Err(Error::Parse(
+            format!("Unexpected '{}', {reason}", &self.input[span.clone()]),
+            Some(span),
+        )))
+    }
 }
 
 impl<'input> Iterator for Parser<'input> {
@@ -273,7 +517,10 @@ mod tests {
 
         assert_eq!(
             parser.next(),
-            Some(Ok(ParsedElement::Text("This is some simple text.")))
+            Some(Ok(ParsedElement::Text(
+                "This is some simple text.".into(),
+                0..25
+            )))
         );
         assert!(parser.next().is_none());
     }
@@ -284,7 +531,10 @@ mod tests {
 
         assert_eq!(
             parser.next(),
-            Some(Ok(ParsedElement::Text("This is some (simple) text.")))
+            Some(Ok(ParsedElement::Text(
+                "This is some (simple) text.".into(),
+                0..27
+            )))
         );
         assert!(parser.next().is_none());
     }
@@ -293,7 +543,10 @@ mod tests {
     fn matching_paren_start_text() {
         let mut parser = Parser::new("(simple)");
 
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::Text("(simple)"))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("(simple)".into(), 0..8)))
+        );
         assert!(parser.next().is_none());
     }
 
@@ -303,12 +556,21 @@ mod tests {
 
         assert_eq!(
             parser.next(),
-            Some(Ok(ParsedElement::Text("This is some simple text.")))
+            Some(Ok(ParsedElement::Text(
+                "This is some simple text.".into(),
+                0..25
+            )))
         );
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::HardLinebreak())));
         assert_eq!(
             parser.next(),
-            Some(Ok(ParsedElement::Text("And this is a new paragraph.")))
+            Some(Ok(ParsedElement::HardLinebreak(25..27)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(
+                "And this is a new paragraph.".into(),
+                27..55
+            )))
         );
         assert!(parser.next().is_none());
     }
@@ -320,9 +582,14 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "test",
+                "test".into(),
+                2..6,
                 vec![],
-                vec![ParsedElement::Text("first"), ParsedElement::Text("second")]
+                vec![
+                    ParsedElement::Text("first".into(), 7..12),
+                    ParsedElement::Text("second".into(), 15..21)
+                ],
+                0..22
             )))
         );
         assert!(parser.next().is_none());
@@ -335,21 +602,28 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "title",
+                "title".into(),
+                2..7,
                 vec![],
-                vec![ParsedElement::Text("Test Document"),]
+                vec![ParsedElement::Text("Test Document".into(), 8..21)],
+                0..22
             )))
         );
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::Text("\n"))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("\n".into(), 22..23)))
+        );
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "authors",
+                "authors".into(),
+                25..32,
                 vec![],
                 vec![
-                    ParsedElement::Text("John Doe"),
-                    ParsedElement::Text("Jane Doe")
-                ]
+                    ParsedElement::Text("John Doe".into(), 33..41),
+                    ParsedElement::Text("Jane Doe".into(), 44..52)
+                ],
+                23..53
             )))
         );
         assert!(parser.next().is_none());
@@ -362,12 +636,17 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "test",
+                "test".into(),
+                2..6,
                 vec![
-                    Attribute::new_flag("abc"),
-                    Attribute::new_value("def", "ghi")
+                    Attribute::new_flag("abc", 8..11),
+                    Attribute::new_value("def", 13..16, "ghi", 17..20)
                 ],
-                vec![ParsedElement::Text("first"), ParsedElement::Text("second")]
+                vec![
+                    ParsedElement::Text("first".into(), 22..27),
+                    ParsedElement::Text("second".into(), 30..36)
+                ],
+                0..37
             )))
         );
         assert!(parser.next().is_none());
@@ -382,13 +661,21 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "quote",
+                "quote".into(),
+                2..7,
                 vec![],
-                vec![ParsedElement::Block(vec![
-                    ParsedElement::Text("Some quote..."),
-                    ParsedElement::HardLinebreak(),
-                    ParsedElement::Text("Spread over multiple paragraphs.\nBecause edgecases!"),
-                ])]
+                vec![ParsedElement::Block(
+                    vec![
+                        ParsedElement::Text("Some quote...".into(), 8..21),
+                        ParsedElement::HardLinebreak(21..23),
+                        ParsedElement::Text(
+                            "Spread over multiple paragraphs.\nBecause edgecases!".into(),
+                            23..74
+                        ),
+                    ],
+                    8..74
+                )],
+                0..76
             )))
         );
         assert!(parser.next().is_none());
@@ -401,16 +688,26 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "list",
+                "list".into(),
+                2..6,
                 vec![],
                 vec![
                     ParsedElement::Function(
-                        "mi",
+                        "mi".into(),
+                        9..11,
                         vec![],
-                        vec![ParsedElement::Text("\\lambda x.M")]
+                        vec![ParsedElement::Text("\\lambda x.M".into(), 12..23)],
+                        7..24
                     ),
-                    ParsedElement::Function("mi", vec![], vec![ParsedElement::Text("(M\\;N)")])
-                ]
+                    ParsedElement::Function(
+                        "mi".into(),
+                        29..31,
+                        vec![],
+                        vec![ParsedElement::Text("(M\\;N)".into(), 32..38)],
+                        27..39
+                    )
+                ],
+                0..40
             )))
         );
         assert!(parser.next().is_none());
@@ -420,9 +717,15 @@ mod tests {
     fn space_after_paragraph() {
         let mut parser = Parser::new("Test \n\nTest");
 
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::Text("Test "))));
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::HardLinebreak())));
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::Text("Test"))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Test ".into(), 0..5)))
+        );
+        assert_eq!(parser.next(), Some(Ok(ParsedElement::HardLinebreak(5..7))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Test".into(), 7..11)))
+        );
         assert!(parser.next().is_none());
     }
 
@@ -430,7 +733,10 @@ mod tests {
     fn special_characters() {
         let mut parser = Parser::new("categorieën");
 
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::Text("categorieën"))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("categorieën".into(), 0..12)))
+        );
         assert!(parser.next().is_none());
     }
 
@@ -450,32 +756,602 @@ mod tests {
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "title",
+                "title".into(),
+                2..7,
+                vec![],
+                vec![ParsedElement::Text("This is some document".into(), 8..29)],
+                0..30
+            )))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::HardLinebreak(30..32)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "table".into(),
+                34..39,
+                vec![
+                    Attribute::new_value("cols", 41..45, "2", 46..47),
+                    Attribute::new_flag("header", 50..56)
+                ],
+                vec![
+                    ParsedElement::Text("Name".into(), 59..63),
+                    ParsedElement::Text("Score".into(), 66..71),
+                    ParsedElement::Text("Apple".into(), 74..79),
+                    ParsedElement::Text("4".into(), 82..83),
+                    ParsedElement::Text("Banana".into(), 86..92),
+                    ParsedElement::Text("8".into(), 95..96),
+                    ParsedElement::Text("Pear".into(), 99..103),
+                    ParsedElement::Text("9".into(), 106..107),
+                ],
+                32..109
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn escaped_characters() {
+        let mut parser = Parser::new("\\[1\\] a \\| b \\#c \\@d \\(e");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("[1] a | b #c @d (e".into(), 0..24)))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn escaped_characters_in_function() {
+        let mut parser = Parser::new("[#test \\] | a\\|b \\@c(\\))]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "test".into(),
+                2..6,
+                vec![],
+                vec![
+                    ParsedElement::Text("]".into(), 7..9),
+                    ParsedElement::Text("a|b @c())".into(), 12..24)
+                ],
+                0..25
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn escaped_attribute_value() {
+        let mut parser = Parser::new("[#test @lang(c\\)) first]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "test".into(),
+                2..6,
+                vec![Attribute::new_value("lang", 8..12, "c)", 13..16)],
+                vec![ParsedElement::Text("first".into(), 18..23)],
+                0..24
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn unescaped_text_is_borrowed() {
+        let mut parser = Parser::new("\\lambda \\[x\\]");
+
+        assert!(matches!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(Cow::Owned(text), _))) if text == "\\lambda [x]"
+        ));
+
+        let mut parser = Parser::new("\\lambda x");
+
+        assert!(matches!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(Cow::Borrowed("\\lambda x"), _)))
+        ));
+    }
+
+    #[test]
+    fn spans_skip_surrounding_whitespace() {
+        let input = "  [#b x ]  ";
+        let mut parser = Parser::new(input);
+
+        let Some(Ok(ParsedElement::Function(name, name_span, _, arguments, span))) = parser.next()
+        else {
+            panic!("Expected a function");
+        };
+
+        assert_eq!(&input[name_span], name);
+        assert_eq!(&input[span], "[#b x ]");
+        assert_eq!(&input[arguments[0].span().clone()], "x");
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn stray_tokens() {
+        for (input, span) in [
+            ("]", 0..1),
+            ("text)", 4..5),
+            ("@attr", 0..5),
+            ("#function", 0..9),
+            ("first | second", 6..7),
+        ] {
+            let error = Parser::new(input).find_map(|e| e.err());
+            assert!(
+                matches!(&error, Some(Error::Parse(_, Some(s))) if *s == span),
+                "{input}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nesting_depth_limit() {
+        let input = "[#b ".repeat(10_000);
+        let error = Parser::new(&input).next();
+
+        assert!(matches!(error, Some(Err(Error::Parse(_, Some(_))))));
+    }
+
+    #[test]
+    fn recover_from_invalid_function() {
+        let mut parser = Parser::new("[#a @b(x] text [#c y]").with_error_recovery();
+
+        assert_eq!(parser.next(), Some(Ok(ParsedElement::Error(0..9))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" text ".into(), 9..15)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "c".into(),
+                17..18,
+                vec![],
+                vec![ParsedElement::Text("y".into(), 19..20)],
+                15..21
+            )))
+        );
+        assert!(parser.next().is_none());
+        assert_eq!(
+            parser.errors(),
+            &[Error::Parse(
+                "Expected token RightParen but got RightBracket".to_string(),
+                Some(8..9)
+            )]
+        );
+    }
+
+    #[test]
+    fn recover_from_unclosed_function() {
+        let mut parser =
+            Parser::new("[#title Doc\n\nSecond paragraph [#b bold]").with_error_recovery();
+
+        assert_eq!(parser.next(), Some(Ok(ParsedElement::Error(0..7))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" Doc".into(), 7..11)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::HardLinebreak(11..13)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Second paragraph ".into(), 13..30)))
+        );
+        assert!(matches!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(name, ..))) if name == "b"
+        ));
+        assert!(parser.next().is_none());
+        assert_eq!(
+            parser.errors(),
+            &[Error::Parse(
+                "Function is missing a closing ']'".to_string(),
+                Some(0..7)
+            )]
+        );
+    }
+
+    #[test]
+    fn recover_at_enclosing_separator() {
+        let mut parser = Parser::new("[#list a | [#b @x( | c]").with_error_recovery();
+
+        let Some(Ok(ParsedElement::Function(name, _, _, args, span))) = parser.next() else {
+            panic!("expected a function");
+        };
+        assert_eq!(name, "list");
+        assert_eq!(span, 0..23);
+        assert_eq!(
+            args,
+            vec![
+                ParsedElement::Text("a".into(), 7..8),
+                ParsedElement::Error(11..19),
+                ParsedElement::Text("c".into(), 21..22),
+            ]
+        );
+        assert!(parser.next().is_none());
+        assert_eq!(parser.errors().len(), 1);
+    }
+
+    #[test]
+    fn recover_unclosed_function_with_arguments() {
+        let mut parser = Parser::new("[#list a | [#b x | c] and more").with_error_recovery();
+
+        assert_eq!(parser.next(), Some(Ok(ParsedElement::Error(0..6))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" a ".into(), 6..9)))
+        );
+        assert_eq!(parser.next(), Some(Ok(ParsedElement::Error(9..10))));
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" ".into(), 10..11)))
+        );
+        let Some(Ok(ParsedElement::Function(name, _, _, args, span))) = parser.next() else {
+            panic!("expected a function");
+        };
+        assert_eq!(name, "b");
+        assert_eq!(span, 11..21);
+        assert_eq!(
+            args,
+            vec![
+                ParsedElement::Text("x".into(), 15..16),
+                ParsedElement::Text("c".into(), 19..20),
+            ]
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" and more".into(), 21..30)))
+        );
+        assert!(parser.next().is_none());
+        assert_eq!(parser.errors().len(), 2);
+    }
+
+    #[test]
+    fn recover_inside_arguments() {
+        let mut parser = Parser::new("[#list a | [#b @x(y] | c @]").with_error_recovery();
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "list".into(),
+                2..6,
+                vec![],
+                vec![
+                    ParsedElement::Text("a".into(), 7..8),
+                    ParsedElement::Error(11..20),
+                    ParsedElement::Block(
+                        vec![
+                            ParsedElement::Text("c ".into(), 23..25),
+                            ParsedElement::Error(25..26)
+                        ],
+                        23..26
+                    )
+                ],
+                0..27
+            )))
+        );
+        assert!(parser.next().is_none());
+        assert_eq!(parser.errors().len(), 2);
+    }
+
+    #[test]
+    fn recover_collects_all_errors() {
+        let mut parser = Parser::new("] a [#b c) d] | e [#f").with_error_recovery();
+
+        assert!(parser.by_ref().all(|e| e.is_ok()));
+        assert_eq!(
+            parser
+                .errors()
+                .iter()
+                .map(|e| e.span().unwrap().clone())
+                .collect::<Vec<_>>(),
+            vec![0..1, 9..10, 14..15, 18..21]
+        );
+    }
+
+    #[test]
+    fn bracket_group() {
+        let mut parser = Parser::new("See [1] and [an [#b optional] note]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("See ".into(), 0..4)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Group(
+                vec![ParsedElement::Text("1".into(), 5..6)],
+                4..7
+            )))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(" and ".into(), 7..12)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Group(
+                vec![
+                    ParsedElement::Text("an ".into(), 13..16),
+                    ParsedElement::Function(
+                        "b".into(),
+                        18..19,
+                        vec![],
+                        vec![ParsedElement::Text("optional".into(), 20..28)],
+                        16..29
+                    ),
+                    ParsedElement::Text(" note".into(), 29..34)
+                ],
+                12..35
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn bracket_group_in_function() {
+        let mut parser = Parser::new("[#list [] | [x]]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "list".into(),
+                2..6,
+                vec![],
+                vec![
+                    ParsedElement::Group(vec![], 7..9),
+                    ParsedElement::Group(vec![ParsedElement::Text("x".into(), 13..14)], 12..15)
+                ],
+                0..16
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn bracket_group_with_separator() {
+        let mut parser = Parser::new("[a | b]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Err(Error::Parse(
+                "Expected RightBracket at the end of bracket group".to_string(),
+                Some(3..4)
+            )))
+        );
+    }
+
+    #[test]
+    fn line_comment() {
+        let mut parser = Parser::new("Some %% a comment\ntext\n%% full line\nmore");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Some text\nmore".into(), 0..40)))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn line_comment_before_paragraph() {
+        let mut parser = Parser::new("Paragraph\n%% comment\n\nNext");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Paragraph\n".into(), 0..10)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::HardLinebreak(21..22)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Next".into(), 22..26)))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn block_comment() {
+        let mut parser = Parser::new("[#b bold %{ spans\n\nparagraphs }%]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "b".into(),
+                2..3,
                 vec![],
-                vec![ParsedElement::Text("This is some document")]
+                vec![ParsedElement::Text("bold".into(), 4..8)],
+                0..33
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn namespaced_function() {
+        let mut parser = Parser::new("[#math.frac @inline a | b]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "math.frac".into(),
+                2..11,
+                vec![Attribute::new_flag("inline", 13..19)],
+                vec![
+                    ParsedElement::Text("a".into(), 20..21),
+                    ParsedElement::Text("b".into(), 24..25)
+                ],
+                0..26
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn unclosed_block_comment() {
+        let mut parser = Parser::new("text %{ never closed");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("text ".into(), 0..5)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Err(Error::Parse(
+                "Block comment is never closed with '}%'".to_string(),
+                Some(5..20)
+            )))
+        );
+    }
+
+    #[test]
+    fn escaped_comment() {
+        let mut parser = Parser::new("50% done \\%% not a comment");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text(
+                "50% done %% not a comment".into(),
+                0..26
             )))
         );
-        assert_eq!(parser.next(), Some(Ok(ParsedElement::HardLinebreak())));
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn keep_comments() {
+        let mut parser = Parser::new("Some %% a comment\ntext").with_comments();
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("Some ".into(), 0..5)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Comment("%% a comment\n".into(), 5..18)))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Text("text".into(), 18..22)))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn comments_between_attributes() {
+        for input in [
+            "[#b @x %{c}% @y z]",
+            "[#b @x(1) %% note\n @y z]",
+            "[#b @x %{c}% | z]",
+        ] {
+            let without_comments: Vec<_> = Parser::new(input).collect();
+            let with_comments: Vec<_> = Parser::new(input).with_comments().collect();
+
+            assert!(matches!(
+                without_comments.as_slice(),
+                [Ok(ParsedElement::Function(..))]
+            ));
+            assert_eq!(with_comments, without_comments);
+        }
+    }
+
+    #[test]
+    fn raw_text() {
+        let input = "[#mi ```\\lambda [x] | y```]";
+        let mut parser = Parser::new(input);
+
+        let Some(Ok(ParsedElement::Function(_, _, _, arguments, _))) = parser.next() else {
+            panic!("Expected a function");
+        };
+
+        assert_eq!(
+            arguments,
+            vec![ParsedElement::Text("\\lambda [x] | y".into(), 8..23)]
+        );
+        assert!(matches!(
+            &arguments[0],
+            ParsedElement::Text(Cow::Borrowed(text), _) if text.as_ptr() == input[8..].as_ptr()
+        ));
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn raw_text_is_not_trimmed() {
+        let mut parser = Parser::new("[#code | ```  x ``` | ```` a ``` b ```` ]");
+
         assert_eq!(
             parser.next(),
             Some(Ok(ParsedElement::Function(
-                "table",
+                "code".into(),
+                2..6,
+                vec![],
                 vec![
-                    Attribute::new_value("cols", "2"),
-                    Attribute::new_flag("header")
+                    ParsedElement::Text("  x ".into(), 12..16),
+                    ParsedElement::Text(" a ``` b ".into(), 26..35),
                 ],
+                0..41
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn raw_text_padding() {
+        let mut parser = Parser::new("[#code | ``` `a` ``` | ```  `b ``` | ``` ```]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "code".into(),
+                2..6,
+                vec![],
                 vec![
-                    ParsedElement::Text("Name"),
-                    ParsedElement::Text("Score"),
-                    ParsedElement::Text("Apple"),
-                    ParsedElement::Text("4"),
-                    ParsedElement::Text("Banana"),
-                    ParsedElement::Text("8"),
-                    ParsedElement::Text("Pear"),
-                    ParsedElement::Text("9"),
-                ]
+                    ParsedElement::Text("`a`".into(), 13..16),
+                    ParsedElement::Text(" `b ".into(), 27..31),
+                    ParsedElement::Text(" ".into(), 40..41),
+                ],
+                0..45
+            )))
+        );
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn raw_attribute_value() {
+        let mut parser = Parser::new("[#code @lang(```r|s```) x]");
+
+        assert_eq!(
+            parser.next(),
+            Some(Ok(ParsedElement::Function(
+                "code".into(),
+                2..6,
+                vec![Attribute::new_value("lang", 8..12, "r|s", 16..19)],
+                vec![ParsedElement::Text("x".into(), 24..25)],
+                0..26
             )))
         );
         assert!(parser.next().is_none());
     }
+
+    #[test]
+    fn unclosed_raw_text() {
+        let mut parser = Parser::new("[#a ```unclosed");
+
+        assert_eq!(
+            parser.next(),
+            Some(Err(Error::Parse(
+                "Raw text is never closed with a matching backtick fence".to_string(),
+                Some(4..15)
+            )))
+        );
+    }
 }
@@ -0,0 +1,82 @@
+use std::fmt::{self, Write};
+
+use crate::{
+    metadata::{FunctionMetadata, ReturnKind},
+    registry::FunctionRegistry,
+};
+
+pub fn to_markdown<C, V>(registry: &FunctionRegistry<C, V>) -> String {
+    let mut output = String::new();
+    // NOTE: writing to a string cannot fail
+    write_markdown(&mut output, registry).unwrap();
+    output
+}
+
+pub fn write_markdown<W: Write, C, V>(
+    output: &mut W,
+    registry: &FunctionRegistry<C, V>,
+) -> fmt::Result {
+    output.write_str("# Function reference\n")?;
+
+    for function in registry.functions() {
+        output.write_char('\n')?;
+        write_function(output, function)?;
+    }
+
+    Ok(())
+}
+
+fn write_function<W: Write>(output: &mut W, function: &FunctionMetadata) -> fmt::Result {
+    writeln!(output, "## `{}`\n", function.name)?;
+
+    if !function.aliases.is_empty() {
+        let aliases: Vec<_> = function.aliases.iter().map(|a| format!("`{a}`")).collect();
+        writeln!(output, "Aliases: {}\n", aliases.join(", "))?;
+    }
+
+    write!(output, "```noet\n[#{}", function.name)?;
+    for (i, argument) in function.arguments.iter().enumerate() {
+        output.write_str(if i == 0 { " " } else { " | " })?;
+        output.write_str(&argument.type_name)?;
+        if argument.variadic {
+            output.write_str("...")?;
+        } else if argument.optional {
+            output.write_char('?')?;
+        }
+    }
+    output.write_str("]\n```\n\n")?;
+
+    if let Some(docs) = &function.docs {
+        writeln!(output, "{docs}\n")?;
+    }
+
+    if !function.attributes.is_empty() {
+        output.write_str("Attributes:\n\n")?;
+        for attribute in &function.attributes {
+            write!(output, "- `@{}`", attribute.key)?;
+            if let Some(value_type) = &attribute.value_type {
+                write!(output, " `{value_type}`")?;
+            }
+            if attribute.required {
+                output.write_str(" (required)")?;
+            }
+            if let Some(docs) = &attribute.docs {
+                write!(output, ": {}", docs.replace('\n', " "))?;
+            }
+            output.write_char('\n')?;
+        }
+        output.write_char('\n')?;
+    }
+
+    let returns = match function.returns.kind {
+        ReturnKind::Nothing => "Returns nothing",
+        ReturnKind::Value => "Returns a value",
+        ReturnKind::OptionalValue => "Returns an optional value",
+    };
+    let fallible = if function.returns.fallible {
+        " and can fail"
+    } else {
+        ""
+    };
+    writeln!(output, "{returns}{fallible}.")
+}
@@ -1,27 +1,205 @@
 use std::collections::HashMap;
 
-use crate::function::{Function, ToFunction};
+use crate::{
+    attribute::Attrs,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    function::{Function, ToFunction},
+    library::FunctionLibrary,
+    metadata::FunctionMetadata,
+    parse_tree::ParsedElement,
+};
 
 #[derive(Default)]
 pub struct FunctionRegistry<Context, Value> {
-    bindings: HashMap<&'static str, Function<Context, Value>>,
+    bindings: HashMap<String, Function<Context, Value>>,
+    aliases: HashMap<String, String>,
+    metadata: HashMap<String, FunctionMetadata>,
+    group_handler: Option<Function<Context, Value>>,
 }
 
 impl<Context, Value> FunctionRegistry<Context, Value> {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            aliases: HashMap::new(),
+            metadata: HashMap::new(),
+            group_handler: None,
         }
     }
 
-    pub fn register_function<F, A, R>(&mut self, func: F, name: &'static str)
+    pub fn register_function<F, A, R>(&mut self, func: F, name: impl Into<String>)
     where
         F: for<'a> ToFunction<'a, Context, Value, A, R>,
     {
-        self.bindings.insert(name, func.to_function());
+        self.register_function_with_metadata(func, FunctionMetadata::new(name));
+    }
+
+    pub fn register_function_with_metadata<F, A, R>(
+        &mut self,
+        func: F,
+        mut metadata: FunctionMetadata,
+    ) where
+        F: for<'a> ToFunction<'a, Context, Value, A, R>,
+    {
+        let signature = F::signature();
+        metadata.arguments = signature.arguments;
+        metadata.returns = signature.returns;
+        metadata.raw = false;
+        // NOTE: attributes declared on the function take precedence over the attribute struct
+        if metadata.attributes.is_empty() {
+            metadata.attributes = signature.attributes;
+        }
+
+        self.insert_function(func.to_function(), metadata);
+    }
+
+    // NOTE: raw functions receive their arguments unevaluated, so the metadata has to describe
+    // the arguments they accept
+    pub fn register_raw_function<F>(&mut self, func: F, metadata: FunctionMetadata)
+    where
+        F: Fn(
+                &Evaluator<Context, Value>,
+                &mut Context,
+                Attrs,
+                Vec<ParsedElement>,
+            ) -> Result<Option<Value>>
+            + 'static,
+    {
+        self.insert_function(
+            Box::new(func),
+            FunctionMetadata {
+                raw: true,
+                ..metadata
+            },
+        );
+    }
+
+    fn insert_function(&mut self, function: Function<Context, Value>, metadata: FunctionMetadata) {
+        // NOTE: registering a name again overrides the previous function and its aliases
+        self.unregister_function(&metadata.name);
+
+        self.bindings.insert(metadata.name.clone(), function);
+        for alias in &metadata.aliases {
+            self.aliases.insert(alias.clone(), metadata.name.clone());
+        }
+        self.metadata.insert(metadata.name.clone(), metadata);
+    }
+
+    pub fn unregister_function(&mut self, name: &str) -> bool {
+        if let Some(target) = self.aliases.remove(name) {
+            if let Some(metadata) = self.metadata.get_mut(&target) {
+                metadata.aliases.retain(|alias| alias != name);
+            }
+            return true;
+        }
+
+        let Some(metadata) = self.metadata.remove(name) else {
+            return false;
+        };
+
+        self.bindings.remove(name);
+        for alias in &metadata.aliases {
+            if self.aliases.get(alias).is_some_and(|target| target == name) {
+                self.aliases.remove(alias);
+            }
+        }
+
+        true
+    }
+
+    pub fn register_library<L>(&mut self, library: &L, namespace: Option<&str>) -> Result<()>
+    where
+        L: FunctionLibrary<Context, Value> + ?Sized,
+    {
+        let mut functions = FunctionRegistry::new();
+        library.register_functions(&mut functions);
+        self.merge(functions, namespace)
+    }
+
+    pub fn merge(&mut self, mut other: Self, namespace: Option<&str>) -> Result<()> {
+        let qualify = |name: &str| match namespace {
+            Some(namespace) => format!("{namespace}.{name}"),
+            None => name.to_string(),
+        };
+
+        // NOTE: conflicts are checked up front so a failed merge leaves the registry untouched
+        let mut conflicts: Vec<_> = other
+            .bindings
+            .keys()
+            .chain(other.aliases.keys())
+            .map(|name| qualify(name))
+            .filter(|name| self.contains(name))
+            .collect();
+        conflicts.sort();
+
+        if !conflicts.is_empty() {
+            let message = match conflicts.as_slice() {
+                [name] => format!("Function '{name}' is already registered"),
+                names => format!("Functions '{}' are already registered", names.join("', '")),
+            };
+            return Err(Error::Eval(message, None));
+        }
+        if self.group_handler.is_some() && other.group_handler.is_some() {
+            return Err(Error::Eval(
+                "A group handler is already registered".to_string(),
+                None,
+            ));
+        }
+
+        for (name, mut metadata) in other.metadata {
+            let function = other.bindings.remove(&name).unwrap();
+
+            metadata.name = qualify(&metadata.name);
+            metadata.aliases = metadata.aliases.iter().map(|a| qualify(a)).collect();
+
+            self.bindings.insert(metadata.name.clone(), function);
+            for alias in &metadata.aliases {
+                self.aliases.insert(alias.clone(), metadata.name.clone());
+            }
+            self.metadata.insert(metadata.name.clone(), metadata);
+        }
+        if other.group_handler.is_some() {
+            self.group_handler = other.group_handler;
+        }
+
+        Ok(())
+    }
+
+    pub fn register_group_handler<F, A, R>(&mut self, func: F)
+    where
+        F: for<'a> ToFunction<'a, Context, Value, A, R>,
+    {
+        self.group_handler = Some(func.to_function());
+    }
+
+    pub fn unregister_group_handler(&mut self) -> bool {
+        self.group_handler.take().is_some()
+    }
+
+    fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.bindings.contains_key(name) || self.aliases.contains_key(name)
     }
 
     pub fn get(&self, name: &str) -> Option<&Function<Context, Value>> {
-        self.bindings.get(name)
+        self.bindings.get(self.resolve(name))
+    }
+
+    pub fn metadata(&self, name: &str) -> Option<&FunctionMetadata> {
+        self.metadata.get(self.resolve(name))
+    }
+
+    pub fn functions(&self) -> Vec<&FunctionMetadata> {
+        let mut functions: Vec<_> = self.metadata.values().collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        functions
+    }
+
+    pub fn group_handler(&self) -> Option<&Function<Context, Value>> {
+        self.group_handler.as_ref()
     }
 }
@@ -1,7 +1,13 @@
-use crate::{error::Result, value::Value};
+use crate::{
+    error::Result,
+    metadata::{ReturnKind, ReturnMetadata},
+    value::Value,
+};
 
 pub trait ReturnValue<V> {
     fn to_result_of_option(self) -> Result<Option<V>>;
+
+    fn metadata() -> ReturnMetadata;
 }
 
 impl<'input, V> ReturnValue<V> for Result<Option<V>>
@@ -11,6 +17,10 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         self
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::OptionalValue, true)
+    }
 }
 
 impl<'input, V> ReturnValue<V> for Result<()>
@@ -20,6 +30,10 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         self.map(|_| None)
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::Nothing, true)
+    }
 }
 
 impl<'input, V> ReturnValue<V> for Result<V>
@@ -29,6 +43,10 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         self.map(Some)
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::Value, true)
+    }
 }
 
 impl<'input, V> ReturnValue<V> for Option<V>
@@ -38,6 +56,10 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         Ok(self)
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::OptionalValue, false)
+    }
 }
 
 impl<'input, V> ReturnValue<V> for V
@@ -47,6 +69,10 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         Ok(Some(self))
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::Value, false)
+    }
 }
 
 impl<'input, V> ReturnValue<V> for ()
@@ -56,4 +82,8 @@ where
     fn to_result_of_option(self) -> Result<Option<V>> {
         Ok(None)
     }
+
+    fn metadata() -> ReturnMetadata {
+        ReturnMetadata::new(ReturnKind::Nothing, false)
+    }
 }
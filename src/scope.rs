@@ -0,0 +1,168 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{lexer::Span, parse_tree::ParsedElement};
+
+pub const SET: &str = "set";
+pub const GET: &str = "get";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Variable {
+    Text(String),
+    Element(ParsedElement<'static>),
+    List(Vec<Variable>),
+}
+
+impl Variable {
+    pub fn to_element<'input>(&self, span: Span) -> ParsedElement<'input> {
+        match self {
+            Variable::Text(text) => ParsedElement::Text(Cow::Owned(text.clone()), span),
+            Variable::Element(element) => element.clone(),
+            Variable::List(items) => {
+                let mut elements = vec![];
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        elements.push(ParsedElement::Text(Cow::Borrowed(", "), span.clone()));
+                    }
+                    elements.push(item.to_element(span.clone()));
+                }
+                ParsedElement::Block(elements, span)
+            }
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Variable::Text(text) => Some(text),
+            Variable::Element(ParsedElement::Text(text, _)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Variable::List(items) => !items.is_empty(),
+            variable => variable
+                .as_text()
+                .is_none_or(|text| !matches!(text.trim(), "" | "false")),
+        }
+    }
+
+    // NOTE: text is treated as a comma separated list
+    pub fn items(&self) -> Vec<Variable> {
+        match (self, self.as_text()) {
+            (Variable::List(items), _) => items.clone(),
+            (_, Some(text)) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(Variable::from)
+                .collect(),
+            (variable, None) => vec![variable.clone()],
+        }
+    }
+}
+
+impl From<String> for Variable {
+    fn from(text: String) -> Self {
+        Variable::Text(text)
+    }
+}
+
+impl From<&str> for Variable {
+    fn from(text: &str) -> Self {
+        Variable::Text(text.to_string())
+    }
+}
+
+impl<T: Into<Variable>> From<Vec<T>> for Variable {
+    fn from(items: Vec<T>) -> Self {
+        Variable::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<ParsedElement<'_>> for Variable {
+    fn from(element: ParsedElement<'_>) -> Self {
+        Variable::Element(element.into_owned())
+    }
+}
+
+pub struct Scope {
+    frames: Vec<HashMap<String, Variable>>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self {
+            frames: vec![HashMap::new()],
+        }
+    }
+
+    pub fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    // NOTE: the outermost frame holds the variables of the document and is never popped
+    pub fn pop(&mut self) {
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Variable>) {
+        // NOTE: there is always at least one frame
+        self.frames
+            .last_mut()
+            .unwrap()
+            .insert(name.into(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_frames() {
+        let mut scope = Scope::new();
+        scope.set("a", "document");
+
+        scope.push();
+        scope.set("a", "inner");
+        scope.set("b", "inner");
+        assert_eq!(scope.get("a"), Some(&Variable::from("inner")));
+
+        scope.pop();
+        scope.pop();
+        assert_eq!(scope.get("a"), Some(&Variable::from("document")));
+        assert_eq!(scope.get("b"), None);
+    }
+
+    #[test]
+    fn variable_items() {
+        assert_eq!(
+            Variable::from("a, b,, c ").items(),
+            vec![
+                Variable::from("a"),
+                Variable::from("b"),
+                Variable::from("c")
+            ]
+        );
+        assert_eq!(
+            Variable::from(vec!["a, b"]).items(),
+            vec![Variable::from("a, b")]
+        );
+        assert!(!Variable::from(" false ").is_truthy());
+        assert!(!Variable::from(Vec::<String>::new()).is_truthy());
+        assert!(Variable::from(ParsedElement::Block(vec![], 0..0)).is_truthy());
+    }
+}
@@ -0,0 +1,206 @@
+use std::borrow::Cow;
+
+use crate::{
+    attribute::Attrs,
+    context::Context,
+    error::{Error, Result},
+    metadata::{AttributeMetadata, FunctionMetadata},
+    parse_tree::ParsedElement,
+    registry::FunctionRegistry,
+    value::Value,
+    variadic::Variadic,
+    writer::to_source,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Element {
+    Text(String),
+    Bold(Box<Element>),
+    Italic(Box<Element>),
+    List(Vec<Element>, bool),
+    Table(Vec<Element>, u32, bool),
+    Link(String, Box<Element>),
+    Code(String, Option<String>),
+    Image(String, Option<String>),
+    Block(Vec<Element>),
+    Linebreak,
+}
+
+impl<'input> Value<'input> for Element {
+    const LINEBREAK: Option<Self> = Some(Element::Linebreak);
+
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self> {
+        Some(Element::Text(text.into_owned()))
+    }
+
+    fn from_block_element(elements: Vec<Self>) -> Option<Self> {
+        Some(Element::Block(elements))
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+    pub title: Option<String>,
+}
+
+// NOTE: custom contexts can reuse the standard functions by giving access to a document
+impl AsMut<Document> for Document {
+    fn as_mut(&mut self) -> &mut Document {
+        self
+    }
+}
+
+impl Context<Element> for Document {
+    fn register_functions(registry: &mut FunctionRegistry<Self, Element>) {
+        std_lib(registry);
+    }
+}
+
+fn metadata(name: &str, docs: &str, attributes: Vec<AttributeMetadata>) -> FunctionMetadata {
+    FunctionMetadata {
+        docs: Some(docs.to_string()),
+        attributes,
+        ..FunctionMetadata::new(name)
+    }
+}
+
+fn attribute(key: &str, docs: &str, value_type: Option<&str>) -> AttributeMetadata {
+    AttributeMetadata {
+        value_type: value_type.map(str::to_string),
+        ..AttributeMetadata::new(key, Some(docs))
+    }
+}
+
+pub fn std_lib<C>(registry: &mut FunctionRegistry<C, Element>)
+where
+    C: AsMut<Document> + 'static,
+{
+    registry.register_function_with_metadata(
+        func_title,
+        metadata("title", "Sets the title of the document.", vec![]),
+    );
+    registry.register_function_with_metadata(
+        func_bold,
+        metadata("b", "Renders its content in bold.", vec![]),
+    );
+    registry.register_function_with_metadata(
+        func_italic,
+        metadata("i", "Renders its content in italics.", vec![]),
+    );
+    registry.register_function_with_metadata(
+        func_list,
+        metadata(
+            "list",
+            "A list with one item per argument.",
+            vec![attribute("ordered", "Numbers the items.", None)],
+        ),
+    );
+    registry.register_function_with_metadata(
+        func_table,
+        metadata(
+            "table",
+            "A table with one cell per argument, filled row by row.",
+            vec![
+                attribute("cols", "The number of columns, 1 by default.", Some("u32")),
+                attribute("header", "Renders the first row as a header.", None),
+            ],
+        ),
+    );
+    registry.register_function_with_metadata(
+        func_link,
+        metadata(
+            "link",
+            "A link to a url, showing the url if no text is given.",
+            vec![],
+        ),
+    );
+    registry.register_function_with_metadata(
+        func_code,
+        metadata(
+            "code",
+            "A block of code, nested functions are kept as source.",
+            vec![attribute(
+                "lang",
+                "The language of the code.",
+                Some("String"),
+            )],
+        ),
+    );
+    registry.register_function_with_metadata(
+        func_image,
+        metadata("image", "An image with an optional description.", vec![]),
+    );
+}
+
+fn func_title<C: AsMut<Document>>(context: &mut C, attrs: Attrs, title: String) -> Result<()> {
+    attrs.deny_unknown(&[])?;
+    context.as_mut().title = Some(title);
+    Ok(())
+}
+
+fn func_bold<C>(_context: &mut C, attrs: Attrs, content: Element) -> Result<Element> {
+    attrs.deny_unknown(&[])?;
+    Ok(Element::Bold(Box::new(content)))
+}
+
+fn func_italic<C>(_context: &mut C, attrs: Attrs, content: Element) -> Result<Element> {
+    attrs.deny_unknown(&[])?;
+    Ok(Element::Italic(Box::new(content)))
+}
+
+fn func_list<C>(_context: &mut C, attrs: Attrs, items: Variadic<Element>) -> Result<Element> {
+    attrs.deny_unknown(&["ordered"])?;
+    Ok(Element::List(items.into(), attrs.get_flag("ordered")?))
+}
+
+fn func_table<C>(_context: &mut C, attrs: Attrs, cells: Variadic<Element>) -> Result<Element> {
+    attrs.deny_unknown(&["cols", "header"])?;
+
+    let cols = attrs.get_value("cols")?.unwrap_or(1);
+    if cols == 0 {
+        return Err(Error::Type(
+            "A table should have at least one column".to_string(),
+            None,
+        ));
+    }
+
+    Ok(Element::Table(
+        cells.into(),
+        cols,
+        attrs.get_flag("header")?,
+    ))
+}
+
+fn func_link<C>(
+    _context: &mut C,
+    attrs: Attrs,
+    url: String,
+    text: Option<Element>,
+) -> Result<Element> {
+    attrs.deny_unknown(&[])?;
+
+    let text = text.unwrap_or_else(|| Element::Text(url.clone()));
+    Ok(Element::Link(url, Box::new(text)))
+}
+
+fn func_code<C>(_context: &mut C, attrs: Attrs, source: ParsedElement<'static>) -> Result<Element> {
+    attrs.deny_unknown(&["lang"])?;
+
+    let source = match source {
+        ParsedElement::Text(text, _) => text.into_owned(),
+        source => to_source(std::slice::from_ref(&source))?,
+    };
+
+    Ok(Element::Code(source, attrs.get_value("lang")?))
+}
+
+fn func_image<C>(
+    _context: &mut C,
+    attrs: Attrs,
+    url: String,
+    description: Option<String>,
+) -> Result<Element> {
+    attrs.deny_unknown(&[])?;
+    Ok(Element::Image(url, description))
+}
@@ -0,0 +1,327 @@
+use std::iter::Peekable;
+
+use crate::{
+    lexer::{Lexer, Span, Token, TokenType},
+    parser::MAX_NESTING_DEPTH,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyntaxKind {
+    Document,
+    Function,
+    Attribute,
+    Argument,
+    Group,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxToken<'input> {
+    pub token_type: TokenType,
+    pub text: &'input str,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxElement<'input> {
+    Node(SyntaxNode<'input>),
+    Token(SyntaxToken<'input>),
+}
+
+impl<'input> SyntaxElement<'input> {
+    pub fn span(&self) -> &Span {
+        match self {
+            SyntaxElement::Node(node) => &node.span,
+            SyntaxElement::Token(token) => &token.span,
+        }
+    }
+
+    fn write_text(&self, output: &mut String) {
+        match self {
+            SyntaxElement::Node(node) => node.write_text(output),
+            SyntaxElement::Token(token) => output.push_str(token.text),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxNode<'input> {
+    pub kind: SyntaxKind,
+    pub span: Span,
+    pub children: Vec<SyntaxElement<'input>>,
+}
+
+impl<'input> SyntaxNode<'input> {
+    pub fn parse(input: &'input str) -> Self {
+        SyntaxBuilder::new(input).document()
+    }
+
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        self.write_text(&mut output);
+        output
+    }
+
+    fn write_text(&self, output: &mut String) {
+        for child in &self.children {
+            child.write_text(output);
+        }
+    }
+
+    fn new(kind: SyntaxKind, children: Vec<SyntaxElement<'input>>, fallback: usize) -> Self {
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => first.span().start..last.span().end,
+            _ => fallback..fallback,
+        };
+
+        Self {
+            kind,
+            span,
+            children,
+        }
+    }
+}
+
+struct SyntaxBuilder<'input> {
+    input: &'input str,
+    tokens: Peekable<Lexer<'input>>,
+    current: usize,
+    depth: usize,
+}
+
+impl<'input> SyntaxBuilder<'input> {
+    fn new(input: &'input str) -> Self {
+        Self {
+            input,
+            tokens: Lexer::new(input).with_comments().peekable(),
+            current: 0,
+            depth: 0,
+        }
+    }
+
+    fn peek_type(&mut self) -> Option<TokenType> {
+        self.tokens.peek().map(|t| t.token_type)
+    }
+
+    fn token(&mut self, token: Token) -> SyntaxElement<'input> {
+        self.current = token.span.end;
+
+        SyntaxElement::Token(SyntaxToken {
+            token_type: token.token_type,
+            text: &self.input[token.span.clone()],
+            span: token.span,
+        })
+    }
+
+    fn consume(&mut self) -> Option<SyntaxElement<'input>> {
+        let token = self.tokens.next()?;
+        Some(self.token(token))
+    }
+
+    fn whitespace(&self, span: Span) -> Option<SyntaxElement<'input>> {
+        (!span.is_empty()).then(|| {
+            SyntaxElement::Token(SyntaxToken {
+                token_type: TokenType::Whitespace,
+                text: &self.input[span.clone()],
+                span,
+            })
+        })
+    }
+
+    fn document(mut self) -> SyntaxNode<'input> {
+        // NOTE: the lexer trims the input, the trimmed whitespace is added back to stay lossless
+        let offset = self.input.len() - self.input.trim_start().len();
+        let mut children: Vec<_> = self.whitespace(0..offset).into_iter().collect();
+
+        while let Some(token) = self.tokens.next() {
+            match token.token_type {
+                TokenType::LeftBracket => {
+                    let open = self.token(token);
+                    children.push(self.bracketed(open));
+                }
+                _ => children.push(self.token(token)),
+            }
+        }
+
+        let end = offset.max(self.current);
+        children.extend(self.whitespace(end..self.input.len()));
+
+        SyntaxNode {
+            kind: SyntaxKind::Document,
+            span: 0..self.input.len(),
+            children,
+        }
+    }
+
+    fn bracketed(&mut self, open: SyntaxElement<'input>) -> SyntaxElement<'input> {
+        // NOTE: brackets nested deeper than the parser allows are kept as flat tokens, so deeply
+        // nested input cannot overflow the stack
+        if self.depth >= MAX_NESTING_DEPTH {
+            return open;
+        }
+
+        self.depth += 1;
+        let node = match self.peek_type() {
+            Some(TokenType::FunctionIdentifier) => self.function(open),
+            _ => self.group(open),
+        };
+        self.depth -= 1;
+
+        SyntaxElement::Node(node)
+    }
+
+    fn content(&mut self, children: &mut Vec<SyntaxElement<'input>>, until: &[TokenType]) {
+        while let Some(token_type) = self.peek_type() {
+            if until.contains(&token_type) {
+                break;
+            }
+
+            // NOTE: unwrapping here is allowed as we first check the next token type before consuming
+            let element = self.consume().unwrap();
+            match token_type {
+                TokenType::LeftBracket => children.push(self.bracketed(element)),
+                _ => children.push(element),
+            }
+        }
+    }
+
+    fn group(&mut self, open: SyntaxElement<'input>) -> SyntaxNode<'input> {
+        let mut children = vec![open];
+
+        self.content(&mut children, &[TokenType::RightBracket]);
+        children.extend(self.consume());
+
+        SyntaxNode::new(SyntaxKind::Group, children, self.current)
+    }
+
+    fn attribute(&mut self) -> SyntaxNode<'input> {
+        let mut children: Vec<_> = self.consume().into_iter().collect();
+
+        if self.peek_type() == Some(TokenType::LeftParen) {
+            children.extend(self.consume());
+
+            let mut paren_depth = 0u32;
+            while let Some(token_type) = self.peek_type() {
+                match token_type {
+                    TokenType::LeftParen => paren_depth += 1,
+                    TokenType::RightParen if paren_depth == 0 => {
+                        children.extend(self.consume());
+                        break;
+                    }
+                    TokenType::RightParen => paren_depth -= 1,
+                    TokenType::Text
+                    | TokenType::Whitespace
+                    | TokenType::Escape
+                    | TokenType::Raw
+                    | TokenType::Comment => {}
+                    _ => break,
+                }
+                children.extend(self.consume());
+            }
+        }
+
+        SyntaxNode::new(SyntaxKind::Attribute, children, self.current)
+    }
+
+    fn function(&mut self, open: SyntaxElement<'input>) -> SyntaxNode<'input> {
+        let mut children = vec![open];
+        children.extend(self.consume());
+
+        loop {
+            match self.peek_type() {
+                Some(TokenType::Whitespace | TokenType::Comment) => children.extend(self.consume()),
+                Some(TokenType::AttributeIdentifier) => {
+                    children.push(SyntaxElement::Node(self.attribute()))
+                }
+                _ => break,
+            }
+        }
+
+        if self.peek_type() == Some(TokenType::ArgumentSeparator) {
+            children.extend(self.consume());
+        }
+
+        while !matches!(self.peek_type(), None | Some(TokenType::RightBracket)) {
+            let start = self.current;
+            let mut argument = vec![];
+            self.content(
+                &mut argument,
+                &[TokenType::ArgumentSeparator, TokenType::RightBracket],
+            );
+            children.push(SyntaxElement::Node(SyntaxNode::new(
+                SyntaxKind::Argument,
+                argument,
+                start,
+            )));
+
+            if self.peek_type() == Some(TokenType::ArgumentSeparator) {
+                children.extend(self.consume());
+            }
+        }
+
+        children.extend(self.consume());
+
+        SyntaxNode::new(SyntaxKind::Function, children, self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossless() {
+        for input in [
+            "",
+            "  Some text  \n",
+            "[#title Document]\n\n[#table @cols(2) @header\n| a | b %% comment\n| c | d\n]",
+            "[#b | [x] %{ block }% | ```raw | text```]\n\nunclosed [#b",
+            "stray ] and | and @attr",
+        ] {
+            assert_eq!(SyntaxNode::parse(input).text(), input);
+        }
+    }
+
+    #[test]
+    fn lossless_deep_nesting() {
+        for input in [
+            "[".repeat(200_000),
+            format!("{}x{}", "[#b ".repeat(200_000), "]".repeat(200_000)),
+        ] {
+            assert_eq!(SyntaxNode::parse(&input).text(), input);
+        }
+    }
+
+    #[test]
+    fn function_structure() {
+        let document = SyntaxNode::parse("[#b @x(1) a | b]");
+
+        let [SyntaxElement::Node(function)] = document.children.as_slice() else {
+            panic!("Expected a single function node");
+        };
+
+        let kinds: Vec<_> = function
+            .children
+            .iter()
+            .map(|c| match c {
+                SyntaxElement::Node(node) => format!("{:?}", node.kind),
+                SyntaxElement::Token(token) => format!("{:?}", token.token_type),
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "LeftBracket",
+                "FunctionIdentifier",
+                "Whitespace",
+                "Attribute",
+                "Whitespace",
+                "Argument",
+                "ArgumentSeparator",
+                "Argument",
+                "RightBracket"
+            ]
+        );
+        assert_eq!(function.span, 0..16);
+    }
+}
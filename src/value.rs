@@ -1,20 +1,23 @@
+use std::borrow::Cow;
+
 pub trait Value<'input>
 where
     Self: Sized,
 {
     const LINEBREAK: Option<Self>;
 
-    fn from_text_element(text: &'input str) -> Option<Self>;
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self>;
 
     fn from_block_element(elements: Vec<Self>) -> Option<Self>;
 }
 
+#[derive(Debug)]
 pub struct EmptyValue {}
 
 impl<'input> Value<'input> for EmptyValue {
     const LINEBREAK: Option<Self> = None;
 
-    fn from_text_element(_text: &'input str) -> Option<Self> {
+    fn from_text_element(_text: Cow<'input, str>) -> Option<Self> {
         None
     }
 
@@ -1,4 +1,7 @@
-use crate::{argument::Argument, error::Result, evaluator::Evaluator, parse_tree::ParsedElement};
+use crate::{
+    argument::Argument, error::Result, evaluator::Evaluator, metadata::ArgumentMetadata,
+    parse_tree::ParsedElement,
+};
 
 pub struct Variadic<T> {
     inner: Vec<T>,
@@ -38,6 +41,13 @@ where
                 .collect::<Result<Vec<_>>>()?,
         })
     }
+
+    fn metadata() -> ArgumentMetadata {
+        ArgumentMetadata {
+            variadic: true,
+            ..T::metadata()
+        }
+    }
 }
 
 impl<T> Variadic<T> {
@@ -0,0 +1,366 @@
+use std::fmt::{self, Write};
+
+use crate::{
+    attribute::Attribute,
+    error::{Error, Result},
+    lexer::Lexer,
+    parse_tree::ParsedElement,
+};
+
+pub fn to_source(elements: &[ParsedElement]) -> Result<String> {
+    let mut output = String::new();
+    // NOTE: writing to a string only fails on elements that failed to parse
+    write_source(&mut output, elements)
+        .map_err(|_| Error::Parse("Cannot write source that failed to parse".to_string(), None))?;
+    Ok(output)
+}
+
+pub fn write_source<W: Write>(output: &mut W, elements: &[ParsedElement]) -> fmt::Result {
+    write_document(output, elements, false)
+}
+
+// NOTE: elements that failed to parse have no source, so they are written as a comment instead of
+// failing, which keeps formatting infallible
+pub(crate) fn write_source_lossy<W: Write>(
+    output: &mut W,
+    elements: &[ParsedElement],
+) -> fmt::Result {
+    write_document(output, elements, true)
+}
+
+fn write_document<W: Write>(
+    output: &mut W,
+    elements: &[ParsedElement],
+    lossy: bool,
+) -> fmt::Result {
+    // NOTE: the lexer trims the input, an empty comment keeps linebreaks at the edges in place
+    let anchor = |element: Option<&ParsedElement>, trim: fn(&str) -> &str| match element {
+        Some(ParsedElement::HardLinebreak(_)) => true,
+        Some(ParsedElement::Text(text, _)) => trim(text) != text && !can_be_raw(text),
+        _ => false,
+    };
+
+    if anchor(elements.first(), str::trim_start) {
+        output.write_str("%{}%")?;
+    }
+    write_elements(output, elements, Position::Document, lossy)?;
+    if anchor(elements.last(), str::trim_end) {
+        output.write_str("%{}%")?;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    Document,
+    Argument,
+    Group,
+}
+
+fn write_elements<W: Write>(
+    output: &mut W,
+    elements: &[ParsedElement],
+    position: Position,
+    lossy: bool,
+) -> fmt::Result {
+    let raw = raw_texts(elements, position);
+
+    for (i, element) in elements.iter().enumerate() {
+        match element {
+            ParsedElement::Text(text, _) if raw[i] => {
+                // NOTE: the fences of adjacent raw texts would run together otherwise
+                if i > 0 && raw[i - 1] {
+                    output.write_str("%{}%")?;
+                }
+                write_raw(output, text)?
+            }
+            ParsedElement::Text(text, _) => write_escaped(output, text)?,
+            ParsedElement::Function(name, _, attrs, args, _) => {
+                write_function(output, name, attrs, args, lossy)?
+            }
+            ParsedElement::HardLinebreak(_) => output.write_str("\n\n")?,
+            ParsedElement::Block(elements, _) => write_elements(output, elements, position, lossy)?,
+            ParsedElement::Group(elements, _) => {
+                output.write_char('[')?;
+                write_elements(output, elements, Position::Group, lossy)?;
+                output.write_char(']')?;
+            }
+            ParsedElement::Comment(comment, _) => {
+                output.write_str(comment)?;
+                if comment.starts_with("%%") && !comment.ends_with('\n') && i + 1 < elements.len() {
+                    output.write_char('\n')?;
+                }
+            }
+            ParsedElement::Error(_) if lossy => output.write_str("%{ error }%")?,
+            ParsedElement::Error(_) => return Err(fmt::Error),
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_attribute<W: Write>(output: &mut W, attribute: &Attribute) -> fmt::Result {
+    write!(output, "@{}", attribute.key)?;
+
+    if let Some(value) = &attribute.value {
+        output.write_char('(')?;
+        if (value.contains("\n\n") || value.ends_with('\\')) && can_be_raw(value) {
+            write_raw(output, value)?;
+        } else {
+            write_escaped(output, value)?;
+        }
+        output.write_char(')')?;
+    }
+
+    Ok(())
+}
+
+fn write_function<W: Write>(
+    output: &mut W,
+    name: &str,
+    attrs: &[Attribute],
+    args: &[ParsedElement],
+    lossy: bool,
+) -> fmt::Result {
+    write!(output, "[#{name}")?;
+
+    for attribute in attrs {
+        output.write_char(' ')?;
+        write_attribute(output, attribute)?;
+    }
+
+    for (i, argument) in args.iter().enumerate() {
+        let elements = match argument {
+            ParsedElement::Block(elements, _) => elements.as_slice(),
+            element => std::slice::from_ref(element),
+        };
+
+        // NOTE: a leading separator is needed when the first argument would otherwise be skipped
+        // or parsed as part of the attributes
+        if i > 0
+            || elements.is_empty()
+            || matches!(
+                elements[0],
+                ParsedElement::Comment(..) | ParsedElement::Error(_)
+            )
+        {
+            output.write_str(" |")?;
+        }
+
+        output.write_char(' ')?;
+        write_elements(output, elements, Position::Argument, lossy)?;
+    }
+
+    output.write_char(']')
+}
+
+fn can_be_raw(text: &str) -> bool {
+    !text.is_empty()
+}
+
+fn raw_texts(elements: &[ParsedElement], position: Position) -> Vec<bool> {
+    let trimmed = position != Position::Group;
+
+    let mut raw: Vec<bool> = elements
+        .iter()
+        .enumerate()
+        .map(|(i, element)| {
+            let ParsedElement::Text(text, _) = element else {
+                return false;
+            };
+
+            let previous = i.checked_sub(1).and_then(|i| elements.get(i));
+            let next = elements.get(i + 1);
+
+            let needs_raw = text.contains("\n\n")
+                || (text.ends_with('\\')
+                    && match next {
+                        Some(ParsedElement::HardLinebreak(_)) => false,
+                        Some(_) => true,
+                        None => position != Position::Document,
+                    })
+                || (trimmed && i == 0 && text.starts_with(char::is_whitespace))
+                || (trimmed && next.is_none() && text.ends_with(char::is_whitespace))
+                || (text.ends_with('\n') && matches!(next, Some(ParsedElement::HardLinebreak(_))))
+                || (text.starts_with('\n')
+                    && matches!(previous, Some(ParsedElement::Comment(c, _)) if c.starts_with("%%")));
+
+            needs_raw && can_be_raw(text)
+        })
+        .collect();
+
+    // NOTE: the parser merges adjacent texts, unless one of them is raw text
+    for i in 1..elements.len() {
+        if let (ParsedElement::Text(previous, _), ParsedElement::Text(current, _)) =
+            (&elements[i - 1], &elements[i])
+        {
+            if raw[i - 1] || raw[i] {
+                continue;
+            }
+
+            // NOTE: prefer the text that does not end up next to another raw text
+            let next_raw = raw.get(i + 1).copied().unwrap_or(false);
+            let previous_raw = i > 1 && raw[i - 2];
+
+            if can_be_raw(current) && (!next_raw || previous_raw || !can_be_raw(previous)) {
+                raw[i] = true;
+            } else {
+                raw[i - 1] = can_be_raw(previous);
+            }
+        }
+    }
+
+    raw
+}
+
+fn write_raw<W: Write>(output: &mut W, text: &str) -> fmt::Result {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest_run + 1).max(3));
+
+    // NOTE: the parser strips one space between the fence and a backtick
+    let start = match text.trim_start_matches(' ').starts_with('`') {
+        true => " ",
+        false => "",
+    };
+    let end = match text.trim_end_matches(' ').ends_with('`') {
+        true => " ",
+        false => "",
+    };
+
+    write!(output, "{fence}{start}{text}{end}{fence}")
+}
+
+fn unmatched_parens(text: &str) -> Vec<usize> {
+    let mut open = vec![];
+    let mut unmatched = vec![];
+    let mut previous = None;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if previous == Some('\\') => {}
+            '(' => open.push(i),
+            ')' if open.pop().is_none() => unmatched.push(i),
+            _ => {}
+        }
+        previous = Some(c);
+    }
+
+    unmatched.extend(open);
+    unmatched
+}
+
+fn write_escaped<W: Write>(output: &mut W, text: &str) -> fmt::Result {
+    let unmatched = unmatched_parens(text);
+    let mut previous = None;
+
+    for (i, c) in text.char_indices() {
+        let next = text[i + c.len_utf8()..].chars().next();
+
+        // NOTE: a backslash in the text would otherwise turn the next character into an escape
+        let escape = Lexer::is_escapable(c)
+            && (previous == Some('\\')
+                || match c {
+                    '(' | ')' => unmatched.contains(&i),
+                    '%' => matches!(next, None | Some('%' | '{')),
+                    _ => true,
+                });
+
+        if escape {
+            output.write_char('\\')?;
+        }
+        output.write_char(c)?;
+        previous = Some(c);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use std::borrow::Cow;
+
+    fn text(text: &str) -> ParsedElement<'_> {
+        ParsedElement::Text(Cow::Borrowed(text), 0..0)
+    }
+
+    #[test]
+    fn escape_reserved_characters() -> Result<()> {
+        assert_eq!(
+            to_source(&[text("[a] | #b @c (d) e) 50% %{ `f` \\[")])?,
+            "\\[a\\] \\| \\#b \\@c (d) e\\) 50% \\%{ \\`f\\` \\\\["
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_text_where_escaping_is_not_enough() -> Result<()> {
+        assert_eq!(to_source(&[text("a\n\nb")])?, "```a\n\nb```");
+        assert_eq!(to_source(&[text("a"), text("b")])?, "a```b```");
+        assert_eq!(to_source(&[text("a ``` b")])?, "a \\`\\`\\` b");
+        assert_eq!(to_source(&[text("a\\")])?, "a\\");
+        assert_eq!(
+            to_source(&[text("a\\"), ParsedElement::Group(vec![], 0..0)])?,
+            "```a\\```[]"
+        );
+        assert_eq!(to_source(&[text("`a\n\nb`")])?, "``` `a\n\nb` ```");
+        assert_eq!(to_source(&[text(" `a\n\nb")])?, "```  `a\n\nb```");
+
+        Ok(())
+    }
+
+    #[test]
+    fn elements_that_failed_to_parse() {
+        assert_eq!(
+            to_source(&[text("a"), ParsedElement::Error(0..0)]),
+            Err(Error::Parse(
+                "Cannot write source that failed to parse".to_string(),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn display_recovered_elements() {
+        let mut parser = Parser::new("[#list a | [#b @x(y] | c][#b").with_error_recovery();
+        let elements: Vec<_> = parser.by_ref().flatten().collect();
+
+        assert_eq!(
+            elements.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["[#list a | %{ error }% | c]", "%{ error }%"]
+        );
+    }
+
+    #[test]
+    fn write_function() {
+        let function = ParsedElement::Function(
+            "table".into(),
+            0..0,
+            vec![
+                Attribute::new_value("cols", 0..0, "2", 0..0),
+                Attribute::new_flag("header", 0..0),
+            ],
+            vec![
+                ParsedElement::Block(vec![], 0..0),
+                text(" padded "),
+                ParsedElement::Block(
+                    vec![text("a "), ParsedElement::Group(vec![text("b")], 0..0)],
+                    0..0,
+                ),
+            ],
+            0..0,
+        );
+
+        assert_eq!(
+            function.to_string(),
+            "[#table @cols(2) @header |  | ``` padded ``` | a [b]]"
+        );
+    }
+}
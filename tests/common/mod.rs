@@ -0,0 +1,50 @@
+// NOTE: every test crate compiles this module, but not every one of them uses all of it
+#![allow(dead_code)]
+
+use std::borrow::Cow;
+
+use noet::{
+    error::Result, evaluator::Evaluator, parser::Parser, registry::FunctionRegistry, value::Value,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct Html(pub String);
+
+impl<'input> Value<'input> for Html {
+    const LINEBREAK: Option<Self> = None;
+
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self> {
+        Some(Html(text.into_owned()))
+    }
+
+    fn from_block_element(elements: Vec<Self>) -> Option<Self> {
+        Some(Html(elements.into_iter().map(|e| e.0).collect()))
+    }
+}
+
+pub fn bold<C: 'static>(registry: &mut FunctionRegistry<C, Html>) {
+    registry.register_function(
+        |_: &mut C, text: Html| Html(format!("<b>{}</b>", text.0)),
+        "b",
+    );
+}
+
+pub fn evaluator<C: 'static>() -> Evaluator<C, Html> {
+    let mut registry = FunctionRegistry::new();
+    bold(&mut registry);
+
+    Evaluator::with_registry(registry)
+}
+
+pub fn render_with<C>(
+    evaluator: &Evaluator<C, Html>,
+    context: &mut C,
+    input: &str,
+) -> Result<String> {
+    let values = evaluator.evaluate_document(context, Parser::new(input))?;
+    Ok(values.into_iter().map(|value| value.0).collect())
+}
+
+pub fn render<C: Default>(evaluator: &Evaluator<C, Html>, input: &str) -> Result<String> {
+    render_with(evaluator, &mut C::default(), input)
+}
@@ -1,6 +1,17 @@
 use noet::{
-    attribute::Attrs, context::Context, error::Result, evaluator::Evaluator, parser::Parser,
-    registry::FunctionRegistry, value::EmptyValue, variadic::Variadic,
+    attribute::Attrs,
+    context::Context,
+    defaulted::Defaulted,
+    diagnostic::Diagnostic,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    metadata::{ArgumentMetadata, FunctionMetadata, ReturnKind, ReturnMetadata},
+    parse_tree::ParsedElement,
+    parser::Parser,
+    reference::to_markdown,
+    registry::FunctionRegistry,
+    value::EmptyValue,
+    variadic::Variadic,
 };
 
 #[derive(Default)]
@@ -10,6 +21,8 @@ pub struct CustomContext {
     pub flag_lang: Option<String>,
     pub flag_export: bool,
     pub variadic_values: Vec<String>,
+    pub citations: Vec<String>,
+    pub links: Vec<(String, Option<String>)>,
 }
 
 impl Context<EmptyValue> for CustomContext {
@@ -19,6 +32,21 @@ impl Context<EmptyValue> for CustomContext {
         registry.register_function(func_attr, "attr");
         registry.register_function(func_flag_attr, "flag-attr");
         registry.register_function(func_variadic, "variadic");
+        registry.register_function(func_table_cols, "table-cols");
+        registry.register_function_with_metadata(
+            func_test,
+            FunctionMetadata {
+                ignore_surplus_arguments: true,
+                ..FunctionMetadata::new("first")
+            },
+        );
+        registry.register_function(func_link, "link");
+        registry.register_function(func_repeat, "repeat");
+        registry.register_function(func_toc, "toc");
+        registry.register_function(func_export, "export");
+        registry.register_function(func_upper, "upper");
+        registry.register_function(func_sum, "sum");
+        registry.register_group_handler(func_citation);
     }
 }
 
@@ -39,6 +67,55 @@ fn func_flag_attr(context: &mut CustomContext, attrs: Attrs, _value: String) {
     context.flag_export = attrs.has_flag("export");
 }
 
+fn func_table_cols(context: &mut CustomContext, attrs: Attrs, _value: String) -> Result<()> {
+    context.version = attrs.get_value("cols")?.unwrap_or(1);
+    Ok(())
+}
+
+fn func_link(context: &mut CustomContext, _attrs: Attrs, url: String, label: Option<String>) {
+    context.links.push((url, label));
+}
+
+fn func_repeat(context: &mut CustomContext, _attrs: Attrs, value: String, count: Defaulted<u32>) {
+    context.value = value.repeat(*count as usize);
+}
+
+fn func_toc(context: &mut CustomContext) {
+    context.value = "toc".to_string();
+}
+
+fn func_export(context: &mut CustomContext, attrs: Attrs) {
+    context.flag_export = attrs.has_flag("export");
+}
+
+fn func_upper(context: &mut CustomContext, value: String) {
+    context.value = value.to_uppercase();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn func_sum(
+    context: &mut CustomContext,
+    _attrs: Attrs,
+    a: u32,
+    b: u32,
+    c: u32,
+    d: u32,
+    e: u32,
+    f: u32,
+    g: u32,
+    h: u32,
+    i: u32,
+    j: u32,
+    k: u32,
+    l: u32,
+) {
+    context.version = a + b + c + d + e + f + g + h + i + j + k + l;
+}
+
+fn func_citation(context: &mut CustomContext, _attrs: Attrs, citation: String) {
+    context.citations.push(citation);
+}
+
 fn func_variadic(context: &mut CustomContext, _attrs: Attrs, args: Variadic<String>) {
     context.variadic_values = args.into();
 }
@@ -133,3 +210,256 @@ fn evaluate_variadic() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn evaluate_argument_type_error_span() {
+    let mut context = CustomContext::default();
+
+    let source = "[#test first]\n[#version abc]";
+    let parser = Parser::new(source);
+
+    let evaluator = Evaluator::new();
+    let error = evaluator
+        .evaluate_document(&mut context, parser)
+        .unwrap_err();
+
+    assert_eq!(error.span(), Some(&(24..27)));
+    assert_eq!(
+        Diagnostic::new(&error, source).to_string(),
+        "error: Type error: Failed to convert 'abc' to u32 in argument
+ --> 2:11
+  |
+2 | [#version abc]
+  |           ^^^"
+    );
+}
+
+#[test]
+fn evaluate_unknown_function_span() {
+    let mut context = CustomContext::default();
+
+    let parser = Parser::new("[#unknown value]");
+
+    let evaluator = Evaluator::new();
+    let error = evaluator
+        .evaluate_document(&mut context, parser)
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        Error::Eval("Function 'unknown' not found".to_string(), Some(2..9))
+    );
+}
+
+#[test]
+fn evaluate_missing_argument_span() {
+    let mut context = CustomContext::default();
+
+    let parser = Parser::new("Text [#version]");
+
+    let evaluator = Evaluator::new();
+    let error = evaluator
+        .evaluate_document(&mut context, parser)
+        .unwrap_err();
+
+    assert_eq!(error.span(), Some(&(5..15)));
+}
+
+#[test]
+fn evaluate_attribute_type_error_span() {
+    let mut context = CustomContext::default();
+
+    let parser = Parser::new("[#attr @lang(rust) some code]");
+
+    let evaluator = Evaluator::new();
+    evaluator.evaluate_document(&mut context, parser).unwrap();
+
+    let parser = Parser::new("[#table-cols @cols(two) some code]");
+    let error = evaluator
+        .evaluate_document(&mut context, parser)
+        .unwrap_err();
+
+    assert_eq!(error.span(), Some(&(19..22)));
+}
+
+#[test]
+fn evaluate_group_handler() -> Result<()> {
+    let mut context = CustomContext::default();
+
+    let parser = Parser::new("As shown in [1] and [2], [#test value]");
+
+    let evaluator = Evaluator::new();
+    evaluator.evaluate_document(&mut context, parser)?;
+
+    assert_eq!(context.citations, vec!["1".to_string(), "2".to_string()]);
+    assert_eq!(context.value, "value");
+
+    Ok(())
+}
+
+#[test]
+fn evaluate_group_handler_without_spans() {
+    let mut context = CustomContext::default();
+    let evaluator = Evaluator::new();
+
+    for elements in [
+        vec![],
+        vec![
+            ParsedElement::Text("3".into(), 0..0),
+            ParsedElement::Group(vec![], 0..0),
+        ],
+    ] {
+        assert_eq!(
+            evaluator
+                .evaluate_element(&mut context, ParsedElement::Group(elements, 0..0))
+                .err(),
+            Some(Error::Type(
+                "Argument of type alloc::string::String should be plain text but got a block"
+                    .to_string(),
+                Some(0..0)
+            ))
+        );
+    }
+}
+
+#[test]
+fn list_registered_functions() {
+    let evaluator = Evaluator::<CustomContext, EmptyValue>::new();
+    let functions = evaluator.registry().functions();
+
+    assert_eq!(
+        functions
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "attr",
+            "export",
+            "first",
+            "flag-attr",
+            "link",
+            "repeat",
+            "sum",
+            "table-cols",
+            "test",
+            "toc",
+            "upper",
+            "variadic",
+            "version"
+        ]
+    );
+
+    let attr = evaluator.registry().metadata("attr").unwrap();
+    assert_eq!(attr.arguments, vec![ArgumentMetadata::new("String")]);
+    assert_eq!(attr.returns, ReturnMetadata::new(ReturnKind::Nothing, true));
+
+    let variadic = evaluator.registry().metadata("variadic").unwrap();
+    assert_eq!(
+        variadic.arguments,
+        vec![ArgumentMetadata {
+            variadic: true,
+            ..ArgumentMetadata::new("String")
+        }]
+    );
+    assert_eq!(
+        variadic.returns,
+        ReturnMetadata::new(ReturnKind::Nothing, false)
+    );
+}
+
+#[test]
+fn evaluate_surplus_arguments() -> Result<()> {
+    let mut context = CustomContext::default();
+    let evaluator = Evaluator::new();
+
+    let error = evaluator
+        .evaluate_document(&mut context, Parser::new("[#test A | B | C]"))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Error::Type(
+            "Function 'test' expects 1 argument but got 3".to_string(),
+            Some(11..12)
+        )
+    );
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#first A | B | C]"))?;
+    assert_eq!(context.value, "A");
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#variadic A | B | C]"))?;
+    assert_eq!(context.variadic_values, vec!["A", "B", "C"]);
+
+    Ok(())
+}
+
+#[test]
+fn evaluate_optional_arguments() -> Result<()> {
+    let mut context = CustomContext::default();
+    let evaluator = Evaluator::new();
+
+    evaluator.evaluate_document(
+        &mut context,
+        Parser::new("[#link example.com][#link example.com | Example]"),
+    )?;
+    assert_eq!(
+        context.links,
+        vec![
+            ("example.com".to_string(), None),
+            ("example.com".to_string(), Some("Example".to_string())),
+        ]
+    );
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#repeat ab]"))?;
+    assert_eq!(context.value, "");
+    evaluator.evaluate_document(&mut context, Parser::new("[#repeat ab | 3]"))?;
+    assert_eq!(context.value, "ababab");
+
+    let link = evaluator.registry().metadata("link").unwrap();
+    assert_eq!(
+        link.arguments,
+        vec![
+            ArgumentMetadata::new("String"),
+            ArgumentMetadata {
+                optional: true,
+                ..ArgumentMetadata::new("String")
+            },
+        ]
+    );
+    assert!(to_markdown(evaluator.registry()).contains("[#link String | String?]"));
+
+    Ok(())
+}
+
+#[test]
+fn evaluate_function_arities() -> Result<()> {
+    let mut context = CustomContext::default();
+    let evaluator = Evaluator::new();
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#toc]"))?;
+    assert_eq!(context.value, "toc");
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#export @export]"))?;
+    assert!(context.flag_export);
+
+    evaluator.evaluate_document(&mut context, Parser::new("[#upper @ignored value]"))?;
+    assert_eq!(context.value, "VALUE");
+
+    evaluator.evaluate_document(
+        &mut context,
+        Parser::new("[#sum 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | 11 | 12]"),
+    )?;
+    assert_eq!(context.version, 78);
+
+    let error = evaluator
+        .evaluate_document(&mut context, Parser::new("[#toc extra]"))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Error::Type(
+            "Function 'toc' expects 0 arguments but got 1".to_string(),
+            Some(6..11)
+        )
+    );
+
+    Ok(())
+}
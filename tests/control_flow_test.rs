@@ -0,0 +1,119 @@
+mod common;
+
+use common::{bold, render_with, Html};
+use noet::{
+    control_flow::control_flow,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    registry::FunctionRegistry,
+};
+
+#[derive(Default)]
+struct Page {
+    visits: Vec<String>,
+}
+
+fn evaluator() -> Result<Evaluator<Page, Html>> {
+    let mut registry = FunctionRegistry::new();
+    registry.register_library(&control_flow, None)?;
+    bold(&mut registry);
+    registry.register_function(
+        |page: &mut Page, name: String| {
+            page.visits.push(name.clone());
+            Html(name)
+        },
+        "visit",
+    );
+
+    Ok(Evaluator::with_registry(registry))
+}
+
+fn render(evaluator: &Evaluator<Page, Html>, input: &str) -> Result<(String, Vec<String>)> {
+    let mut page = Page::default();
+    let output = render_with(evaluator, &mut page, input)?;
+    Ok((output, page.visits))
+}
+
+#[test]
+fn conditionals() -> Result<()> {
+    let mut evaluator = evaluator()?;
+    evaluator.set_variable("status", "draft");
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set draft | true][#if draft | [#visit a] | [#visit b]][#if !draft | [#visit c]]"
+        )?,
+        ("a".to_string(), vec!["a".to_string()])
+    );
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#if draft | [#visit a] | [#visit b]][#if status == draft | [#b c]]\
+             [#if status != draft | d | e]"
+        )?,
+        ("b<b>c</b>e".to_string(), vec!["b".to_string()])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn loops() -> Result<()> {
+    let mut evaluator = evaluator()?;
+    evaluator.set_variable("tags", vec!["rust", "docs"]);
+
+    assert_eq!(
+        render(&evaluator, "[#for tag in tags | [#b [#visit [#get tag]]]]")?,
+        (
+            "<b>rust</b><b>docs</b>".to_string(),
+            vec!["rust".to_string(), "docs".to_string()]
+        )
+    );
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#for tag in tags | [#b [#get tag]]][#set letters | a, b][#for l in letters | <[#get l]>]"
+        )?,
+        ("<b>rust</b><b>docs</b><a><b>".to_string(), vec![])
+    );
+    assert_eq!(
+        render(&evaluator, "[#for tag in tags | x][#get tag]"),
+        Err(Error::Eval(
+            "Variable 'tag' is not defined".to_string(),
+            Some(28..31)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#for tags | x]"),
+        Err(Error::Eval(
+            "Expected a loop of the form 'item in list' but got 'tags'".to_string(),
+            Some(0..15)
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn control_flow_in_variables() -> Result<()> {
+    let mut evaluator = evaluator()?;
+    evaluator.set_variable("tags", vec!["rust", "docs"]);
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set out | [#for t in tags | <[#get t]>]][#get out]"
+        )?,
+        ("<rust><docs>".to_string(), vec![])
+    );
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set x | [#if missing | [#get missing] | [#visit b]]][#get x][#get x]"
+        )?,
+        ("bb".to_string(), vec!["b".to_string(), "b".to_string()])
+    );
+
+    Ok(())
+}
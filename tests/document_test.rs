@@ -1,6 +1,9 @@
+use std::borrow::Cow;
+
 use noet::{
-    attribute::Attrs, context::Context, error::Result, evaluator::Evaluator, parser::Parser,
-    registry::FunctionRegistry, value::Value, variadic::Variadic,
+    attribute::Attrs, context::Context, error::Result, evaluator::Evaluator,
+    parse_tree::ParsedElement, parser::Parser, registry::FunctionRegistry, value::Value,
+    variadic::Variadic,
 };
 
 #[derive(Debug, PartialEq)]
@@ -16,8 +19,8 @@ pub enum Element {
 impl<'input> Value<'input> for Element {
     const LINEBREAK: Option<Self> = Some(Element::Linebreak());
 
-    fn from_text_element(text: &'input str) -> Option<Self> {
-        Some(Self::Text(text.to_string()))
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self> {
+        Some(Self::Text(text.into_owned()))
     }
 
     fn from_block_element(elements: Vec<Self>) -> Option<Self> {
@@ -36,6 +39,7 @@ impl Context<Element> for Note {
         registry.register_function(func_bold, "b");
         registry.register_function(func_list, "list");
         registry.register_function(func_table, "table");
+        registry.register_function(func_source, "source");
     }
 }
 
@@ -59,6 +63,10 @@ fn func_table(_context: &mut Note, attrs: Attrs, items: Variadic<Element>) -> Re
     ))
 }
 
+fn func_source(_context: &mut Note, _attrs: Attrs, element: ParsedElement<'static>) -> Element {
+    Element::Text(element.to_string())
+}
+
 fn parse_document(doc: &str) -> Result<(Note, Vec<Element>)> {
     let parser = Parser::new(doc);
 
@@ -129,3 +137,72 @@ It also supports tables!
 
     Ok(())
 }
+
+#[test]
+fn bracket_group_as_literal_text() -> Result<()> {
+    let (_, elements) = parse_document("See [1] or [[#b this]].")?;
+
+    assert_eq!(
+        elements,
+        vec![
+            Element::Text("See ".to_string()),
+            Element::Block(vec![
+                Element::Text("[".to_string()),
+                Element::Text("1".to_string()),
+                Element::Text("]".to_string()),
+            ]),
+            Element::Text(" or ".to_string()),
+            Element::Block(vec![
+                Element::Text("[".to_string()),
+                Element::Bold(Box::new(Element::Text("this".to_string()))),
+                Element::Text("]".to_string()),
+            ]),
+            Element::Text(".".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn owned_document_across_threads() -> Result<()> {
+    let source = "[#title Owned]\n\nSome [#b bold] text.".to_string();
+    let document = Parser::new(&source)
+        .map(|element| element.map(ParsedElement::into_owned))
+        .collect::<Result<Vec<_>>>()?;
+    drop(source);
+
+    let handle = std::thread::spawn(move || {
+        let mut context = Note::default();
+        let evaluator = Evaluator::new();
+        let evaluated = evaluator.evaluate_document(&mut context, document.into_iter().map(Ok));
+
+        evaluated.map(|elements| (context.title, elements))
+    });
+    let (title, elements) = handle.join().unwrap()?;
+
+    assert_eq!(title, Some("Owned".to_string()));
+    assert_eq!(
+        elements,
+        vec![
+            Element::Linebreak(),
+            Element::Text("Some ".to_string()),
+            Element::Bold(Box::new(Element::Text("bold".to_string()))),
+            Element::Text(" text.".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unevaluated_element_argument() -> Result<()> {
+    let (_, elements) = parse_document("[#source [#b @x(1) bold] and \\| more]")?;
+
+    assert_eq!(
+        elements,
+        vec![Element::Text("[#b @x(1) bold] and \\| more".to_string())]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use noet::{
+    error::Result, format::Formatter, parse_tree::ParsedElement, parser::Parser, syntax::SyntaxNode,
+};
+use proptest::prelude::*;
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Text(String),
+    Function(String, Vec<String>, Vec<Shape>),
+    HardLinebreak,
+    Block(Vec<Shape>),
+    Group(Vec<Shape>),
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut output = String::new();
+    let mut whitespace = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            whitespace = true;
+            continue;
+        }
+        if std::mem::take(&mut whitespace) {
+            output.push(' ');
+        }
+        output.push(c);
+    }
+    if whitespace {
+        output.push(' ');
+    }
+
+    output
+}
+
+// NOTE: the formatter is free to move whitespace around, so whitespace runs are collapsed and
+// whitespace next to paragraph breaks is ignored when comparing
+fn shape(elements: Vec<ParsedElement>, trim_edges: bool) -> Vec<Shape> {
+    let mut shapes: Vec<Shape> = vec![];
+
+    for element in elements {
+        let shape = match element {
+            ParsedElement::Text(text, _) => match shapes.last_mut() {
+                Some(Shape::Text(previous)) => {
+                    previous.push_str(&text);
+                    continue;
+                }
+                _ => Shape::Text(text.into_owned()),
+            },
+            ParsedElement::Function(name, _, attrs, args, _) => Shape::Function(
+                name.to_string(),
+                attrs
+                    .iter()
+                    .map(|a| format!("{}={:?}", a.key, a.value))
+                    .collect(),
+                args.into_iter()
+                    .flat_map(|arg| shape(vec![arg], true))
+                    .collect(),
+            ),
+            ParsedElement::HardLinebreak(_) => Shape::HardLinebreak,
+            ParsedElement::Block(elements, _) => Shape::Block(shape(elements, true)),
+            ParsedElement::Group(elements, _) => Shape::Group(shape(elements, false)),
+            ParsedElement::Comment(..) | ParsedElement::Error(_) => continue,
+        };
+        shapes.push(shape);
+    }
+
+    let len = shapes.len();
+    let breaks: Vec<_> = shapes
+        .iter()
+        .map(|s| matches!(s, Shape::HardLinebreak))
+        .collect();
+
+    shapes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, shape)| match shape {
+            Shape::Text(text) => {
+                let mut text = collapse_whitespace(&text);
+                if (i == 0 && trim_edges) || (i > 0 && breaks[i - 1]) {
+                    text = text.trim_start().to_string();
+                }
+                if (i == len - 1 && trim_edges) || breaks.get(i + 1) == Some(&true) {
+                    text = text.trim_end().to_string();
+                }
+                (!text.is_empty()).then_some(Shape::Text(text))
+            }
+            shape => Some(shape),
+        })
+        .collect()
+}
+
+fn parse(input: &str) -> Result<Vec<Shape>> {
+    Ok(shape(Parser::new(input).collect::<Result<Vec<_>>>()?, true))
+}
+
+fn check_round_trip(input: &str) {
+    let formatter = Formatter::new().with_width(30);
+
+    assert_eq!(SyntaxNode::parse(input).text(), input);
+
+    let Ok(expected) = parse(input) else {
+        assert!(formatter.format(input).is_err());
+        return;
+    };
+
+    let formatted = formatter.format(input).unwrap();
+    assert_eq!(
+        parse(&formatted).unwrap(),
+        expected,
+        "formatted source:\n{formatted}"
+    );
+    assert_eq!(formatter.format(&formatted).unwrap(), formatted);
+}
+
+fn word() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => "[a-z]{1,8}",
+        1 => Just("\\[".to_string()),
+        1 => Just("\\|".to_string()),
+        1 => Just("(x y)".to_string()),
+        1 => Just("```raw | [x]  y```".to_string()),
+        1 => Just("%{ block  comment }%".to_string()),
+        1 => Just("%% line comment\n".to_string()),
+    ]
+}
+
+fn whitespace() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => Just(" ".to_string()),
+        1 => Just("\n".to_string()),
+        1 => Just("  \t".to_string()),
+    ]
+}
+
+fn sequence(item: impl Strategy<Value = String>, size: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec((item, whitespace()), 0..size)
+        .prop_map(|items| items.into_iter().map(|(i, w)| i + &w).collect())
+}
+
+fn attribute() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "@[a-c]{1,3}",
+        "@[a-c]{1,3}\\([a-z ]{0,5}\\)",
+        Just("@raw(```a ) b```)".to_string()),
+    ]
+}
+
+fn inline() -> impl Strategy<Value = String> {
+    word().prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            (
+                "[a-e]{1,3}",
+                prop::collection::vec(attribute(), 0..3),
+                prop::collection::vec(sequence(inner.clone(), 4), 0..4),
+                any::<bool>(),
+            )
+                .prop_map(|(name, attrs, args, multiline)| {
+                    let mut source = format!("[#{name}");
+                    for attr in attrs {
+                        source.push(' ');
+                        source.push_str(&attr);
+                    }
+                    for (i, arg) in args.iter().enumerate() {
+                        source.push_str(match (multiline, i) {
+                            (true, _) => "\n| ",
+                            (false, 0) => " ",
+                            (false, _) => " | ",
+                        });
+                        source.push_str(arg);
+                    }
+                    source.push_str(if multiline { "\n]" } else { "]" });
+                    source
+                }),
+            sequence(inner, 3).prop_map(|content| format!("[{content}]")),
+        ]
+    })
+}
+
+fn document() -> impl Strategy<Value = String> {
+    prop::collection::vec(sequence(inline(), 8), 0..4).prop_map(|p| p.join("\n\n"))
+}
+
+#[test]
+fn format_examples() {
+    for input in [
+        "[#title Document]\n\n[#table @cols(2) @header\n| Name | Score\n| Apple | 4\n]",
+        "[#quote\nSome quote...\n\nSpread over multiple paragraphs.\nBecause edgecases!\n]",
+        "Some text %% with a comment\n\nAnd a [group with [#b nested] functions]",
+        "[#b | ]  [#c |  | ]  [#d x |]",
+        "%{ only a comment }%",
+    ] {
+        check_round_trip(input);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn format_round_trip(input in document()) {
+        check_round_trip(&input);
+    }
+}
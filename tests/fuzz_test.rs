@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+
+use noet::{
+    attribute::Attrs, context::Context, control_flow::control_flow, error::Result,
+    evaluator::Evaluator, parser::Parser, registry::FunctionRegistry, value::Value,
+    variadic::Variadic,
+};
+use proptest::prelude::*;
+
+#[derive(Debug)]
+pub enum Element {
+    Text(String),
+    Block(Vec<Element>),
+    Linebreak(),
+}
+
+impl<'input> Value<'input> for Element {
+    const LINEBREAK: Option<Self> = Some(Element::Linebreak());
+
+    fn from_text_element(text: Cow<'input, str>) -> Option<Self> {
+        Some(Self::Text(text.into_owned()))
+    }
+
+    fn from_block_element(elements: Vec<Self>) -> Option<Self> {
+        Some(Self::Block(elements))
+    }
+}
+
+#[derive(Default)]
+pub struct FuzzContext {}
+
+impl Context<Element> for FuzzContext {
+    fn register_functions(registry: &mut FunctionRegistry<Self, Element>) {
+        registry.register_function(func_string, "a");
+        registry.register_function(func_number, "c");
+        registry.register_function(func_element, "d");
+        registry.register_function(func_variadic, "e");
+        registry.register_function(func_attributes, "g");
+        control_flow(registry);
+    }
+}
+
+fn func_string(_context: &mut FuzzContext, _attrs: Attrs, text: String) -> Element {
+    Element::Text(text)
+}
+
+fn func_number(_context: &mut FuzzContext, _attrs: Attrs, first: u32, second: i8) {
+    let _ = (first, second);
+}
+
+fn func_element(_context: &mut FuzzContext, _attrs: Attrs, element: Element) -> Element {
+    element
+}
+
+fn func_variadic(_context: &mut FuzzContext, _attrs: Attrs, items: Variadic<Element>) -> Element {
+    Element::Block(items.into())
+}
+
+fn func_attributes(_context: &mut FuzzContext, attrs: Attrs, _text: String) -> Result<()> {
+    let _: Option<u32> = attrs.get_value("a")?;
+    let _: Option<bool> = attrs.get_value("b")?;
+    let _ = attrs.has_flag("c");
+    Ok(())
+}
+
+fn noet_fragment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("[".to_string()),
+        Just("]".to_string()),
+        Just("(".to_string()),
+        Just(")".to_string()),
+        Just("|".to_string()),
+        Just("\\".to_string()),
+        Just("\n\n".to_string()),
+        Just("%%".to_string()),
+        Just("%{".to_string()),
+        Just("}%".to_string()),
+        Just("```".to_string()),
+        "[ \t\n]{1,3}",
+        "#[a-g]{0,2}",
+        "@[a-c]{0,2}",
+        "[a-z0-9]{1,5}",
+        "[^\\[\\]()|#@ \t\n]{1,3}",
+        "[x-z]".prop_map(|name| format!("[#define {name} | ")),
+        "[x-z]".prop_map(|name| format!("[#set {name} | ")),
+        "[x-z]".prop_map(|name| format!("[#get {name}]")),
+        "[x-z]".prop_map(|name| format!("[#{name}]")),
+        "!?[x-z]".prop_map(|condition| format!("[#if {condition} | ")),
+        "[x-z]".prop_map(|name| format!("[#for {name} in list | ")),
+    ]
+}
+
+fn noet_source() -> impl Strategy<Value = String> {
+    prop::collection::vec(noet_fragment(), 0..48).prop_map(|fragments| fragments.concat())
+}
+
+fn parse_and_evaluate(source: &str) {
+    for element in Parser::new(source).flatten() {
+        assert!(element.span().end <= source.len());
+    }
+
+    let mut parser = Parser::new(source).with_error_recovery();
+    assert!(parser.by_ref().all(|e| e.is_ok()));
+    if Parser::new(source).any(|e| e.is_err()) {
+        assert!(!parser.errors().is_empty());
+    }
+
+    let mut context = FuzzContext::default();
+    let mut evaluator = Evaluator::new();
+    evaluator.set_variable("list", vec!["a", "b"]);
+    let _ = evaluator.evaluate_document(&mut context, Parser::new(source));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn parser_never_panics_on_noet_like_input(source in noet_source()) {
+        parse_and_evaluate(&source);
+    }
+
+    #[test]
+    fn parser_never_panics_on_arbitrary_input(source in any::<String>()) {
+        parse_and_evaluate(&source);
+    }
+}
+
+#[test]
+fn known_panicking_inputs() {
+    for source in [
+        "]",
+        ")",
+        "@",
+        "#",
+        "|",
+        "[#a [#b 1]]",
+        "[#b [#a 1]]",
+        "[#c [#a 1] | 2]",
+        "[#c 1 | [#d x]]",
+        "[#g @a([#a x]) x]",
+        "[#a",
+        "[#",
+        "[",
+        "[]",
+        "[#a @",
+        "[#a @b(",
+        "[#a \\",
+        "\\",
+    ] {
+        parse_and_evaluate(source);
+    }
+}
+
+#[test]
+fn deeply_nested_input() {
+    let source = format!("{}x{}", "[#d ".repeat(127), "]".repeat(127));
+    parse_and_evaluate(&source);
+
+    let source = format!("{}x{}", "[#d ".repeat(100_000), "]".repeat(100_000));
+    parse_and_evaluate(&source);
+}
+
+#[test]
+fn expanding_input() {
+    let body = format!("{}[#r]{}", "[#d ".repeat(40), "]".repeat(40));
+    parse_and_evaluate(&format!("[#define r | {body}][#r]"));
+
+    let doubling: String = (1..=21)
+        .map(|i| format!("[#define m{i} | [#m{}][#m{}]]", i - 1, i - 1))
+        .collect();
+    parse_and_evaluate(&format!("[#define m0 | a]{doubling}[#m21]"));
+
+    let doubling = "[#set x | [#get x][#get x]]\n".repeat(22);
+    parse_and_evaluate(&format!("[#set x | a]\n{doubling}[#get x]"));
+
+    let nesting = "[#set x | [#d [#get x]]]\n".repeat(5000);
+    parse_and_evaluate(&format!("[#set x | a]\n{nesting}[#get x]"));
+
+    parse_and_evaluate("[#set x | [#for t in list | [#get t]]][#set y | [#if z | [#get z]]]");
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{render, Html};
+use noet::{
+    error::{Error, Result},
+    evaluator::Evaluator,
+    library::FunctionLibrary,
+    metadata::FunctionMetadata,
+    registry::FunctionRegistry,
+};
+
+struct Math {
+    class: &'static str,
+}
+
+impl FunctionLibrary<(), Html> for Math {
+    fn register_functions(&self, registry: &mut FunctionRegistry<(), Html>) {
+        let class = self.class;
+        registry.register_function(
+            move |_: &mut (), a: Html, b: Html| {
+                Html(format!("<span class=\"{class}\">{}/{}</span>", a.0, b.0))
+            },
+            "frac",
+        );
+        registry.register_function(|_: &mut (), a: Html| Html(format!("√{}", a.0)), "sqrt");
+    }
+}
+
+fn text(registry: &mut FunctionRegistry<(), Html>) {
+    registry.register_function_with_metadata(
+        |_: &mut (), text: Html| Html(format!("<b>{}</b>", text.0)),
+        FunctionMetadata {
+            aliases: vec!["bold".to_string()],
+            ..FunctionMetadata::new("b")
+        },
+    );
+}
+
+#[test]
+fn namespaced_libraries() -> Result<()> {
+    let mut registry = FunctionRegistry::new();
+    registry.register_library(&text, None)?;
+    registry.register_library(&Math { class: "frac" }, Some("math"))?;
+    registry.register_library(&text, Some("text"))?;
+
+    assert_eq!(
+        registry
+            .functions()
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["b", "math.frac", "math.sqrt", "text.b"]
+    );
+    assert_eq!(
+        registry.metadata("text.bold").map(|f| &f.aliases),
+        Some(&vec!["text.bold".to_string()])
+    );
+
+    assert_eq!(
+        render(
+            &Evaluator::with_registry(registry),
+            "[#math.frac 1 | [#math.sqrt 2]] [#bold a] [#text.bold b]"
+        )?,
+        "<span class=\"frac\">1/√2</span> <b>a</b> <b>b</b>"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn library_conflicts() -> Result<()> {
+    let mut registry = FunctionRegistry::new();
+    registry.register_library(&Math { class: "a" }, Some("math"))?;
+    registry.register_library(&text, None)?;
+
+    assert_eq!(
+        registry.register_library(&Math { class: "b" }, Some("math")),
+        Err(Error::Eval(
+            "Functions 'math.frac', 'math.sqrt' are already registered".to_string(),
+            None
+        ))
+    );
+    assert_eq!(
+        registry.register_library(
+            &|registry: &mut FunctionRegistry<(), Html>| {
+                registry.register_function(|_: &mut (), text: Html| text, "italic");
+                registry.register_function(|_: &mut (), text: Html| text, "bold");
+            },
+            None
+        ),
+        Err(Error::Eval(
+            "Function 'bold' is already registered".to_string(),
+            None
+        ))
+    );
+
+    // NOTE: a failed merge does not register any of the library's functions
+    assert!(!registry.contains("italic"));
+    assert_eq!(
+        render(&Evaluator::with_registry(registry), "[#math.frac 1 | 2]")?,
+        "<span class=\"a\">1/2</span>"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use common::{bold, render, Html};
+use noet::{
+    attribute::Attrs,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    registry::FunctionRegistry,
+};
+
+fn evaluator() -> Evaluator<(), Html> {
+    let mut registry = FunctionRegistry::new();
+    bold(&mut registry);
+    registry.register_function(
+        |_: &mut (), text: Html| Html(format!("<i>{}</i>", text.0)),
+        "i",
+    );
+    registry.register_function(
+        |_: &mut (), attrs: Attrs, text: Html| {
+            let class = attrs.get_value::<String>("class")?.unwrap_or_default();
+            Ok(Html(format!("<span class=\"{class}\">{}</span>", text.0)))
+        },
+        "span",
+    );
+
+    Evaluator::with_registry(registry)
+}
+
+#[test]
+fn positional_parameters() -> Result<()> {
+    let evaluator = evaluator();
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define shout | [#b [#i $1]]][#define pair | $2 and [#shout $1] for $$1]\
+             [#shout hey] [#pair a | [#i b]]"
+        )?,
+        "<b><i>hey</i></b> <i>b</i> and <b><i>a</i></b> for $1"
+    );
+    assert_eq!(evaluator.macro_definition("pair").unwrap().parameters, 2);
+
+    Ok(())
+}
+
+#[test]
+fn attribute_passthrough() -> Result<()> {
+    let evaluator = evaluator();
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define note | [#span [#b Note:]] [#span $1]][#note @class(info) [#span text]]"
+        )?,
+        "<span class=\"info\"><b>Note:</b></span> <span class=\"info\"><span class=\"\">text</span></span>"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hygiene() -> Result<()> {
+    let evaluator = evaluator();
+
+    // NOTE: arguments are not expanded again and nested definitions keep their own parameters
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define outer | [#define inner | [#i $1]][#b $1]][#outer $1][#inner x]"
+        )?,
+        "<b>$1</b><i>x</i>"
+    );
+
+    assert_eq!(
+        render(&evaluator, "[#define b | [#i $1]]"),
+        Err(Error::Eval(
+            "Cannot define macro 'b', a function with that name already exists".to_string(),
+            Some(9..10)
+        ))
+    );
+    for name in ["define", "set", "get"] {
+        assert_eq!(
+            render(&evaluator, &format!("[#define {name} | x]")),
+            Err(Error::Eval(
+                format!("Cannot define macro '{name}', a function with that name already exists"),
+                Some(9..9 + name.len())
+            ))
+        );
+    }
+    assert_eq!(
+        render(&evaluator, "[#define my macro | x]"),
+        Err(Error::Eval(
+            "Invalid macro name 'my macro'".to_string(),
+            Some(9..17)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#define shout]"),
+        Err(Error::Eval(
+            "A macro definition expects a name and a body".to_string(),
+            Some(0..15)
+        ))
+    );
+
+    // NOTE: macros do not leak into the next document
+    assert_eq!(
+        render(&evaluator, "[#define shout | [#b $1]][#shout a]")?,
+        "<b>a</b>"
+    );
+    assert_eq!(
+        render(&evaluator, "[#shout a]"),
+        Err(Error::Eval(
+            "Function 'shout' not found".to_string(),
+            Some(2..7)
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn macro_arity() {
+    let evaluator = evaluator();
+
+    assert_eq!(
+        render(&evaluator, "[#define shout | [#b $1]][#shout a | b]"),
+        Err(Error::Type(
+            "Macro 'shout' expects 1 argument but got 2".to_string(),
+            Some(37..38)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#define pair | $1 $2][#pair a]"),
+        Err(Error::Type(
+            "Macro 'pair' expects 2 arguments but got 1".to_string(),
+            Some(22..31)
+        ))
+    );
+}
+
+#[test]
+fn recursion_limit() -> Result<()> {
+    let mut evaluator = evaluator();
+
+    assert_eq!(
+        render(&evaluator, "[#define loop | [#b [#loop]]][#loop]"),
+        Err(Error::Eval(
+            "Macro 'loop' exceeded the recursion limit of 64".to_string(),
+            Some(20..27)
+        ))
+    );
+
+    evaluator.set_macro_recursion_limit(2);
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define one | [#b $1]][#define two | [#one $1]][#two a]"
+        )?,
+        "<b>a</b>"
+    );
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define one | [#b $1]][#define two | [#one $1]][#define three | [#two $1]][#three a]"
+        ),
+        Err(Error::Eval(
+            "Macro 'one' exceeded the recursion limit of 2".to_string(),
+            Some(38..47)
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn evaluation_depth_limit() {
+    let evaluator = evaluator();
+    let body = format!("{}[#r]{}", "[#b ".repeat(8), "]".repeat(8));
+
+    assert_eq!(
+        render(&evaluator, &format!("[#define r | {body}][#r]")),
+        Err(Error::Eval(
+            "Evaluation can not be nested more than 256 levels deep".to_string(),
+            Some(25..54)
+        ))
+    );
+}
+
+#[test]
+fn expansion_limit() -> Result<()> {
+    let mut evaluator = evaluator();
+    let doubling: String = (1..=20)
+        .map(|i| format!("[#define m{i} | [#m{}][#m{}]]", i - 1, i - 1))
+        .collect();
+
+    assert_eq!(
+        render(&evaluator, &format!("[#define m0 | a]{doubling}[#m20]")),
+        Err(Error::Eval(
+            "Macro 'm0' exceeded the expansion limit of 10000".to_string(),
+            Some(30..35)
+        ))
+    );
+
+    evaluator.set_macro_expansion_limit(7);
+    assert_eq!(
+        render(&evaluator, &format!("[#define m0 | a]{doubling}[#m2]"))?,
+        "aaaa"
+    );
+    assert!(render(&evaluator, &format!("[#define m0 | a]{doubling}[#m3]")).is_err());
+
+    Ok(())
+}
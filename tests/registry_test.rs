@@ -0,0 +1,112 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{render, Html};
+use noet::{
+    attribute::Attrs,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    metadata::FunctionMetadata,
+    registry::FunctionRegistry,
+};
+
+#[derive(Default)]
+struct Page {
+    links: u32,
+}
+
+fn link_registry(base_url: String) -> FunctionRegistry<Page, Html> {
+    let mut registry = FunctionRegistry::new();
+
+    registry.register_function(
+        move |context: &mut Page, _attrs: Attrs, path: String| {
+            context.links += 1;
+            Html(format!("<a href=\"{base_url}/{path}\">{path}</a>"))
+        },
+        "link",
+    );
+    registry.register_function(
+        |_: &mut Page, text: Html| Html(text.0.to_uppercase()),
+        "upper",
+    );
+
+    registry
+}
+
+#[test]
+fn capturing_closures() -> Result<()> {
+    let evaluator = Evaluator::with_registry(link_registry("https://example.com".to_string()));
+
+    assert_eq!(
+        render(&evaluator, "See [#link docs] or [#upper [#link faq]].")?,
+        "See <a href=\"https://example.com/docs\">docs</a> or \
+         <A HREF=\"HTTPS://EXAMPLE.COM/FAQ\">FAQ</A>."
+    );
+
+    Ok(())
+}
+
+#[test]
+fn runtime_names() -> Result<()> {
+    let mut registry = FunctionRegistry::new();
+    let calls = Rc::new(RefCell::new(vec![]));
+
+    for level in 1..=3 {
+        let calls = calls.clone();
+        registry.register_function(
+            move |_: &mut Page, title: String| {
+                calls.borrow_mut().push(level);
+                Html(format!("<h{level}>{title}</h{level}>"))
+            },
+            format!("h{level}"),
+        );
+    }
+
+    let evaluator = Evaluator::with_registry(registry);
+
+    assert_eq!(
+        render(&evaluator, "[#h1 A][#h3 B]")?,
+        "<h1>A</h1><h3>B</h3>"
+    );
+    assert_eq!(*calls.borrow(), vec![1, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn override_and_unregister() -> Result<()> {
+    let mut evaluator = Evaluator::with_registry(link_registry("https://a.com".to_string()));
+
+    evaluator.registry_mut().register_function_with_metadata(
+        |_: &mut Page, path: String| Html(format!("[{path}]")),
+        FunctionMetadata {
+            aliases: vec!["a".to_string()],
+            ..FunctionMetadata::new("link")
+        },
+    );
+    assert_eq!(render(&evaluator, "[#link x][#a y]")?, "[x][y]");
+
+    assert!(evaluator.registry_mut().unregister_function("a"));
+    assert_eq!(render(&evaluator, "[#link x]")?, "[x]");
+    assert_eq!(
+        render(&evaluator, "[#a y]"),
+        Err(Error::Eval(
+            "Function 'a' not found".to_string(),
+            Some(2..3)
+        ))
+    );
+
+    assert!(evaluator.registry_mut().unregister_function("link"));
+    assert!(!evaluator.registry_mut().unregister_function("link"));
+    assert!(evaluator.registry().metadata("link").is_none());
+    assert_eq!(
+        render(&evaluator, "[#link x]"),
+        Err(Error::Eval(
+            "Function 'link' not found".to_string(),
+            Some(2..6)
+        ))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "serde")]
+
+use noet::{error::Error, parse_tree::ParsedElement, parser::Parser};
+use serde_json::json;
+
+const SOURCE: &str = "[#title Document]
+
+[#table @cols(2) @header(```a | b```)
+| Name | Sc\\|ore
+]";
+
+fn parse(input: &str) -> Vec<ParsedElement<'_>> {
+    Parser::new(input).collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn json_round_trip() {
+    let json = serde_json::to_string(&parse(SOURCE)).unwrap();
+
+    // NOTE: deserializing into an owned tree does not borrow from the json string
+    let owned: Vec<ParsedElement<'static>> = serde_json::from_str(&json).unwrap();
+    drop(json);
+
+    assert_eq!(owned, parse(SOURCE));
+}
+
+#[test]
+fn json_ast_dump() {
+    let value = serde_json::to_value(parse("[#b @x(1) bold]")).unwrap();
+
+    assert_eq!(
+        value,
+        json!([{
+            "Function": [
+                "b",
+                { "start": 2, "end": 3 },
+                [{
+                    "key": "x",
+                    "value": "1",
+                    "key_span": { "start": 5, "end": 6 },
+                    "value_span": { "start": 7, "end": 8 }
+                }],
+                [{ "Text": ["bold", { "start": 10, "end": 14 }] }],
+                { "start": 0, "end": 15 }
+            ]
+        }])
+    );
+}
+
+#[test]
+fn error_round_trip() {
+    let error = Parser::new("[#b unclosed").find_map(Result::err).unwrap();
+
+    let json = serde_json::to_string(&error).unwrap();
+    let deserialized: Error = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized, error);
+}
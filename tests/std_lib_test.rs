@@ -0,0 +1,219 @@
+#![cfg(feature = "std-lib")]
+
+use noet::{
+    context::Context,
+    error::{Error, Result},
+    evaluator::Evaluator,
+    parser::Parser,
+    reference::to_markdown,
+    registry::FunctionRegistry,
+    std_lib::{std_lib, Document, Element},
+};
+
+fn text(text: &str) -> Element {
+    Element::Text(text.to_string())
+}
+
+fn evaluate<C>(
+    evaluator: &Evaluator<C, Element>,
+    context: &mut C,
+    input: &str,
+) -> Result<Vec<Element>> {
+    evaluator.evaluate_document(context, Parser::new(input))
+}
+
+#[test]
+fn standard_vocabulary() -> Result<()> {
+    let source = r#"[#title Notes]
+
+Some [#b bold] and [#i [#link https://example.com | italic]] text, see [#link https://noet.dev].
+
+[#list @ordered
+| first
+| [#image cat.png | A cat]
+]
+
+[#table @cols(2) @header
+| Name | Score
+| Apple | 4
+]
+
+[#code @lang(rust) [#b fn] main]"#;
+
+    let mut document = Document::default();
+    let elements = evaluate(&Evaluator::new(), &mut document, source)?;
+
+    assert_eq!(document.title, Some("Notes".to_string()));
+    assert_eq!(
+        elements,
+        vec![
+            Element::Linebreak,
+            text("Some "),
+            Element::Bold(Box::new(text("bold"))),
+            text(" and "),
+            Element::Italic(Box::new(Element::Link(
+                "https://example.com".to_string(),
+                Box::new(text("italic"))
+            ))),
+            text(" text, see "),
+            Element::Link(
+                "https://noet.dev".to_string(),
+                Box::new(text("https://noet.dev"))
+            ),
+            text("."),
+            Element::Linebreak,
+            Element::List(
+                vec![
+                    text("first"),
+                    Element::Image("cat.png".to_string(), Some("A cat".to_string())),
+                ],
+                true
+            ),
+            Element::Linebreak,
+            Element::Table(
+                vec![text("Name"), text("Score"), text("Apple"), text("4")],
+                2,
+                true
+            ),
+            Element::Linebreak,
+            Element::Code("[#b fn] main".to_string(), Some("rust".to_string())),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn variables_as_text() -> Result<()> {
+    let mut document = Document::default();
+    let elements = evaluate(
+        &Evaluator::new(),
+        &mut document,
+        "[#set t | Notes][#set host | noet.dev][#title [#get t]][#link https://[#get host]/docs]",
+    )?;
+
+    assert_eq!(document.title, Some("Notes".to_string()));
+    assert_eq!(
+        elements,
+        vec![Element::Link(
+            "https://noet.dev/docs".to_string(),
+            Box::new(text("https://noet.dev/docs"))
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_attributes() {
+    let evaluator = Evaluator::new();
+
+    assert_eq!(
+        evaluate(&evaluator, &mut Document::default(), "[#table @cols(0) a]"),
+        Err(Error::Type(
+            "A table should have at least one column".to_string(),
+            Some(0..19)
+        ))
+    );
+    assert_eq!(
+        evaluate(&evaluator, &mut Document::default(), "[#list @numbered a]").err(),
+        Some(Error::Type(
+            "Unknown attribute 'numbered', expected one of: ordered".to_string(),
+            Some(8..16)
+        ))
+    );
+}
+
+#[test]
+fn unknown_attributes() {
+    let evaluator = Evaluator::new();
+
+    for input in [
+        "[#title @x Doc]",
+        "[#b @x a]",
+        "[#i @x a]",
+        "[#list @x a]",
+        "[#table @x a]",
+        "[#link @x https://example.com]",
+        "[#code @x a]",
+        "[#image @x https://example.com/a.png]",
+    ] {
+        let error = evaluate(&evaluator, &mut Document::default(), input).err();
+        assert!(
+            matches!(
+                &error,
+                Some(Error::Type(message, _)) if message.starts_with("Unknown attribute 'x'")
+            ),
+            "{input}: {error:?}"
+        );
+    }
+}
+
+#[derive(Default)]
+struct Blog {
+    document: Document,
+    tags: Vec<String>,
+}
+
+impl AsMut<Document> for Blog {
+    fn as_mut(&mut self) -> &mut Document {
+        &mut self.document
+    }
+}
+
+impl Context<Element> for Blog {
+    fn register_functions(registry: &mut FunctionRegistry<Self, Element>) {
+        std_lib(registry);
+
+        registry.register_function(|blog: &mut Blog, tag: String| blog.tags.push(tag), "tag");
+        registry.register_function(
+            |_: &mut Blog, content: Element| Element::Italic(Box::new(content)),
+            "b",
+        );
+    }
+}
+
+#[test]
+fn extended_vocabulary() -> Result<()> {
+    let mut blog = Blog::default();
+    let elements = evaluate(
+        &Evaluator::new(),
+        &mut blog,
+        "[#title Post][#tag rust][#b not bold][#i italic]",
+    )?;
+
+    assert_eq!(blog.document.title, Some("Post".to_string()));
+    assert_eq!(blog.tags, vec!["rust".to_string()]);
+    assert_eq!(
+        elements,
+        vec![
+            Element::Italic(Box::new(text("not bold"))),
+            Element::Italic(Box::new(text("italic"))),
+        ]
+    );
+
+    let mut evaluator = Evaluator::<Document, Element>::new();
+    evaluator.registry_mut().register_function(
+        |_: &mut Document, url: String| Element::Image(url, None),
+        "image",
+    );
+    assert_eq!(
+        evaluate(&evaluator, &mut Document::default(), "[#image a.png]")?,
+        vec![Element::Image("a.png".to_string(), None)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn documented_vocabulary() {
+    let reference = to_markdown(Evaluator::<Document, Element>::new().registry());
+
+    assert!(reference.contains(
+        "## `table`\n\n```noet\n[#table Element...]\n```\n\n\
+         A table with one cell per argument, filled row by row.\n\n\
+         Attributes:\n\n\
+         - `@cols` `u32`: The number of columns, 1 by default.\n\
+         - `@header`: Renders the first row as a header.\n"
+    ));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{render, Html};
+use noet::{
+    error::{Error, Result},
+    evaluator::{Evaluator, UnknownFunctionPolicy},
+};
+
+const INPUT: &str = "[#b [#unknown @flag a | [#b b]]] c [#missing]";
+
+fn evaluator(policy: UnknownFunctionPolicy<(), Html>) -> Evaluator<(), Html> {
+    let mut evaluator = common::evaluator();
+    evaluator.set_unknown_function_policy(policy);
+    evaluator
+}
+
+#[test]
+fn error_policy() {
+    assert_eq!(
+        render(&evaluator(UnknownFunctionPolicy::Error), INPUT),
+        Err(Error::Eval(
+            "Function 'unknown' not found".to_string(),
+            Some(6..13)
+        ))
+    );
+}
+
+#[test]
+fn warn_policy() -> Result<()> {
+    let warnings = Rc::new(RefCell::new(vec![]));
+    let evaluator = evaluator(UnknownFunctionPolicy::warn({
+        let warnings = warnings.clone();
+        move |error: &Error| warnings.borrow_mut().push(error.span().cloned())
+    }));
+
+    assert_eq!(render(&evaluator, INPUT)?, "<b>a<b>b</b></b> c ");
+    assert_eq!(*warnings.borrow(), vec![Some(6..13), Some(37..44)]);
+
+    Ok(())
+}
+
+#[test]
+fn drop_policy() -> Result<()> {
+    let evaluator = evaluator(UnknownFunctionPolicy::Drop);
+
+    assert_eq!(
+        render(&evaluator, "[#b x [#unknown a] y] c [#missing]")?,
+        "<b>x  y</b> c "
+    );
+    // NOTE: a dropped function does not produce a value for the function it is passed to
+    assert_eq!(
+        render(&evaluator, INPUT),
+        Err(Error::Eval(
+            "Evaluating argument of type unknown_function_test::common::Html did not result in a value"
+                .to_string(),
+            Some(4..31)
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn handler_policy() -> Result<()> {
+    let evaluator = evaluator(UnknownFunctionPolicy::<(), Html>::handler(
+        |evaluator, context, name, attrs, arguments| {
+            let mut content = String::new();
+            for argument in arguments {
+                if let Some(value) = evaluator.evaluate_element(context, argument)? {
+                    content.push_str(&value.0);
+                }
+            }
+            let class = if attrs.has_flag("flag") { " flag" } else { "" };
+
+            Ok(Some(Html(format!(
+                "<span class=\"{name}{class}\">{content}</span>"
+            ))))
+        },
+    ));
+
+    assert_eq!(
+        render(&evaluator, INPUT)?,
+        "<b><span class=\"unknown flag\">a<b>b</b></span></b> c \
+         <span class=\"missing\"></span>"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn handler_errors() {
+    let evaluator = evaluator(UnknownFunctionPolicy::<(), Html>::handler(
+        |_, _, name, _, _| Err(Error::Eval(format!("Cannot render '{name}'"), None)),
+    ));
+
+    assert_eq!(
+        render(&evaluator, "x [#missing]"),
+        Err(Error::Eval(
+            "Cannot render 'missing'".to_string(),
+            Some(2..12)
+        ))
+    );
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::{evaluator, render, Html};
+use noet::{
+    error::{Error, Result},
+    metadata::FunctionMetadata,
+    parse_tree::ParsedElement,
+    parser::Parser,
+    scope::Variable,
+};
+
+#[test]
+fn set_and_get() -> Result<()> {
+    let evaluator = evaluator::<()>();
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set title | My [#b notes]][#get title], again: [#get title]"
+        )?,
+        "My <b>notes</b>, again: My <b>notes</b>"
+    );
+    assert_eq!(render(&evaluator, "[#set title | Notes]")?, "");
+    assert_eq!(
+        evaluator.variable("title"),
+        Some(Variable::Element(ParsedElement::Text(
+            "Notes".into(),
+            14..19
+        )))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lexical_scoping() -> Result<()> {
+    let evaluator = evaluator::<()>();
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set x | outer][#b [#set x | inner][#get x]] [#get x]"
+        )?,
+        "<b>inner</b> outer"
+    );
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set x | a][#set x | [#get x]b][#set y | [#b [#get x]]][#set x | c][#get y]"
+        )?,
+        "<b>ab</b>"
+    );
+    // NOTE: macro bodies are resolved when they are expanded
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#define greet | Hi [#get name]][#set name | Bob][#greet] [#b [#set name | Ann][#greet]]"
+        )?,
+        "Hi Bob <b>Hi Ann</b>"
+    );
+    assert_eq!(
+        render(&evaluator, "[#b [#set x | inner]x][#get x]"),
+        Err(Error::Eval(
+            "Variable 'x' is not defined".to_string(),
+            Some(28..29)
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn seeded_variables() -> Result<()> {
+    let mut evaluator = evaluator::<()>();
+    evaluator.set_variable("title", "Front matter");
+
+    assert_eq!(render(&evaluator, "[#get title]")?, "Front matter");
+    assert_eq!(
+        render(&evaluator, "[#set title | Document][#get title]")?,
+        "Document"
+    );
+    assert_eq!(
+        render(&evaluator, "[#b [#get title]]")?,
+        "<b>Front matter</b>"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_variables() {
+    let evaluator = evaluator::<()>();
+
+    assert_eq!(
+        render(&evaluator, "[#set title]"),
+        Err(Error::Eval(
+            "A variable definition expects a name and a value".to_string(),
+            Some(0..12)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#set a b | c]"),
+        Err(Error::Eval(
+            "Invalid variable name 'a b'".to_string(),
+            Some(6..9)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#get a | b]"),
+        Err(Error::Eval(
+            "A variable reference expects a name".to_string(),
+            Some(0..12)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, "[#set a | [#get b]]"),
+        Err(Error::Eval(
+            "Variable 'b' is not defined".to_string(),
+            Some(16..17)
+        ))
+    );
+}
+
+#[test]
+fn growing_variables() {
+    let evaluator = evaluator::<()>();
+    let doubling = "[#set x | [#get x][#get x]]\n".repeat(22);
+    let nesting = "[#set x | [#b [#get x]]]\n".repeat(5000);
+
+    assert_eq!(
+        render(&evaluator, &format!("[#set x | a]\n{doubling}[#get x]")),
+        Err(Error::Eval(
+            "Variable 'x' exceeds the limit of 10000 elements or 256 levels".to_string(),
+            Some(349..376)
+        ))
+    );
+    assert_eq!(
+        render(&evaluator, &format!("[#set x | a]\n{nesting}[#get x]")),
+        Err(Error::Eval(
+            "Variable 'x' exceeds the limit of 10000 elements or 256 levels".to_string(),
+            Some(6388..6412)
+        ))
+    );
+}
+
+#[test]
+fn nested_documents() -> Result<()> {
+    let mut evaluator = evaluator::<()>();
+    evaluator.registry_mut().register_raw_function(
+        |evaluator, context, _attrs, _arguments| {
+            let source = "[#set x | 2][#define m | inner][#m][#get x]";
+            let values = evaluator.evaluate_document(context, Parser::new(source))?;
+            Ok(Some(Html(
+                values.into_iter().map(|value| value.0).collect(),
+            )))
+        },
+        FunctionMetadata::new("include"),
+    );
+
+    assert_eq!(
+        render(
+            &evaluator,
+            "[#set x | 1][#define m | outer][#include] [#get x][#m]"
+        )?,
+        "inner2 1outer"
+    );
+
+    Ok(())
+}
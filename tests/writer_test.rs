@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+
+use noet::{
+    attribute::Attribute, error::Result, parse_tree::ParsedElement, parser::Parser,
+    writer::to_source,
+};
+use proptest::prelude::*;
+
+fn strip<'a>(elements: Vec<ParsedElement<'a>>) -> Vec<ParsedElement<'a>> {
+    elements.into_iter().map(without_spans).collect()
+}
+
+fn without_spans(element: ParsedElement) -> ParsedElement {
+    match element {
+        ParsedElement::Text(text, _) => ParsedElement::Text(text, 0..0),
+        ParsedElement::Function(name, _, attrs, args, _) => ParsedElement::Function(
+            name,
+            0..0,
+            attrs
+                .into_iter()
+                .map(|a| Attribute {
+                    key_span: 0..0,
+                    value_span: a.value_span.map(|_| 0..0),
+                    ..a
+                })
+                .collect(),
+            strip(args),
+            0..0,
+        ),
+        ParsedElement::HardLinebreak(_) => ParsedElement::HardLinebreak(0..0),
+        ParsedElement::Block(elements, _) => ParsedElement::Block(strip(elements), 0..0),
+        ParsedElement::Group(elements, _) => ParsedElement::Group(strip(elements), 0..0),
+        ParsedElement::Comment(comment, _) => ParsedElement::Comment(comment, 0..0),
+        ParsedElement::Error(_) => ParsedElement::Error(0..0),
+    }
+}
+
+fn parse(input: &str, comments: bool) -> Result<Vec<ParsedElement<'_>>> {
+    let parser = if comments {
+        Parser::new(input).with_comments()
+    } else {
+        Parser::new(input)
+    };
+
+    parser.map(|element| element.map(without_spans)).collect()
+}
+
+fn check_round_trip(elements: Vec<ParsedElement>, comments: bool) {
+    let source = to_source(&elements).unwrap();
+    let reparsed = parse(&source, comments);
+
+    assert_eq!(
+        reparsed.as_ref().ok(),
+        Some(&elements),
+        "written source: {source:?}\nreparsed: {reparsed:?}"
+    );
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[a-z \n\t()\\[\\]|#@%{}`\\\\]{1,8}"
+}
+
+#[derive(Debug, Clone)]
+enum Tree {
+    Text(String),
+    Function(
+        &'static str,
+        Vec<(&'static str, Option<String>)>,
+        Vec<Vec<Tree>>,
+    ),
+    HardLinebreak,
+    Group(Vec<Tree>),
+}
+
+// NOTE: the parser never produces adjacent texts without raw text in between, so they are merged
+fn to_elements(trees: Vec<Tree>) -> Vec<ParsedElement<'static>> {
+    let mut elements: Vec<ParsedElement> = vec![];
+
+    for tree in trees {
+        let element = match tree {
+            Tree::Text(text) => match elements.last_mut() {
+                Some(ParsedElement::Text(previous, _)) => {
+                    previous.to_mut().push_str(&text);
+                    continue;
+                }
+                _ => ParsedElement::Text(Cow::Owned(text), 0..0),
+            },
+            Tree::Function(name, attrs, args) => ParsedElement::Function(
+                name.into(),
+                0..0,
+                attrs
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        Some(value) => Attribute::new_value(key, 0..0, value, 0..0),
+                        None => Attribute::new_flag(key, 0..0),
+                    })
+                    .collect(),
+                args.into_iter()
+                    .map(|arg| {
+                        let mut arg = to_elements(arg);
+                        match arg.len() {
+                            1 => arg.remove(0),
+                            _ => ParsedElement::Block(arg, 0..0),
+                        }
+                    })
+                    .collect(),
+                0..0,
+            ),
+            Tree::HardLinebreak => ParsedElement::HardLinebreak(0..0),
+            Tree::Group(trees) => ParsedElement::Group(to_elements(trees), 0..0),
+        };
+        elements.push(element);
+    }
+
+    elements
+}
+
+fn attribute() -> impl Strategy<Value = (&'static str, Option<String>)> {
+    (
+        prop_oneof![Just("a"), Just("key"), Just("b-2")],
+        prop::option::of("[a-z ()\\[\\]|@`\\\\]{0,6}"),
+    )
+}
+
+fn tree() -> impl Strategy<Value = Tree> {
+    let leaf = prop_oneof![
+        4 => text().prop_map(Tree::Text),
+        1 => Just(Tree::HardLinebreak),
+    ];
+
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            (
+                prop_oneof![Just("b"), Just("table"), Just("x-1")],
+                prop::collection::vec(attribute(), 0..3),
+                prop::collection::vec(prop::collection::vec(inner.clone(), 0..4), 0..4),
+            )
+                .prop_map(|(name, attrs, args)| Tree::Function(name, attrs, args)),
+            prop::collection::vec(inner, 0..4).prop_map(Tree::Group),
+        ]
+    })
+}
+
+fn noet_fragment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("[".to_string()),
+        Just("]".to_string()),
+        Just("(".to_string()),
+        Just(")".to_string()),
+        Just("|".to_string()),
+        Just("\\".to_string()),
+        Just("\n\n".to_string()),
+        Just("%%".to_string()),
+        Just("%{".to_string()),
+        Just("}%".to_string()),
+        Just("```".to_string()),
+        "[ \t\n]{1,3}",
+        "#[a-g]{0,2}",
+        "@[a-c]{0,2}",
+        "[a-z0-9%`]{1,5}",
+    ]
+}
+
+#[test]
+fn write_known_edge_cases() {
+    for input in [
+        "[#title Document]\n\n[#table @cols(2) @header\n| Name | Score\n| Apple | 4\n]",
+        "Escaped \\[brackets\\] and a ```raw [#text]``` and \\| more",
+        "[#b @value(```a (b```) | ```  padded  ``` | [group] text]",
+        "text %% comment\n\nmore %{ block }% text",
+        "[#b | %{ leading comment }% x]",
+    ] {
+        for comments in [false, true] {
+            check_round_trip(parse(input, comments).unwrap(), comments);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn write_generated_trees(trees in prop::collection::vec(tree(), 0..6)) {
+        check_round_trip(to_elements(trees), false);
+    }
+
+    #[test]
+    fn write_parsed_trees(fragments in prop::collection::vec(noet_fragment(), 0..24)) {
+        let input = fragments.concat();
+
+        for comments in [false, true] {
+            if let Ok(elements) = parse(&input, comments) {
+                check_round_trip(elements, comments);
+            }
+        }
+    }
+}